@@ -1,6 +1,10 @@
 from fafreplay._fafreplay import *
+from fafreplay.arrow import write_parquet
+from fafreplay import callbacks, render
+from fafreplay.callbacks import pings, transfers
 
 import base64
+import collections.abc
 import json
 import zlib
 try:
@@ -8,6 +12,8 @@ try:
 except ImportError:
     zstd = None
 
+collections.abc.Mapping.register(LuaTable)
+
 
 def extract_scfa(fobj):
     """extract_scfa(fobj: io.BytesIO) -> bytes
@@ -30,3 +36,36 @@ def extract_scfa(fobj):
                 "zstd is required for decompressing this replay"
             )
         return zstd.decompress(buf)
+
+
+def uses_sim_mod(header, uid):
+    """uses_sim_mod(header: dict, uid: str) -> bool
+
+    Checks whether a sim mod with the given uid was active in the replay. UI
+    mods are ignored since they don't affect the simulation. The uid is
+    compared case insensitively.
+    """
+    uid = uid.lower()
+    return any(
+        mod.uid.lower() == uid and not mod.ui_only
+        for mod in header["mod_info"]
+    )
+
+
+def dump_json(data, path, parser=None):
+    """dump_json(data: bytes, path: str, parser: Optional[Parser] = None) -> None
+
+    Parses a replay and writes the result to `path` as JSON. See
+    `Parser.parse_json` for how the data is converted. By default all commands
+    are included.
+    """
+    if parser is None:
+        parser = Parser(
+            commands=range(commands.MAX + 1),
+            save_commands=True,
+            stop_on_desync=False
+        )
+
+    # serde_json leaves non ASCII characters unescaped
+    with open(path, "w", encoding="utf-8") as f:
+        f.write(parser.parse_json(data))
@@ -0,0 +1,168 @@
+"""
+Command line tool for inspecting replay files.
+
+    python -m fafreplay info 12345.fafreplay
+"""
+
+import argparse
+import json
+import sys
+import zlib
+from datetime import timedelta
+
+from fafreplay import (
+    Parser,
+    ReplayReadError,
+    body_offset,
+    body_ticks,
+    commands,
+    extract_scfa
+)
+
+
+def read_fafreplay(f, path):
+    """Like `extract_scfa` but with a readable error for invalid files"""
+    try:
+        return extract_scfa(f)
+    except (ValueError, zlib.error) as e:
+        raise RuntimeError(f"{path} is not a valid .fafreplay file ({e})")
+
+
+def load_replay(path):
+    """Read a `.scfareplay` or `.fafreplay` file into `.scfareplay` data"""
+    with open(path, "rb") as f:
+        # The .fafreplay format starts with a json header
+        if f.peek(1)[:1] == b"{":
+            return read_fafreplay(f, path)
+        return f.read()
+
+
+def format_ticks(ticks):
+    return str(timedelta(milliseconds=ticks * 100))
+
+
+def print_info(args):
+    data = load_replay(args.replay)
+    offset = body_offset(data)
+    header = Parser().parse_header(data)
+    body = Parser(stop_on_desync=False).parse_body(data[offset:])
+    sim = body["sim"]
+
+    print("Map:", header["map_file"])
+    print("Version:", header["scfa_version"])
+    print("Duration:", format_ticks(sim["tick"]))
+    print("Players:")
+    for source, army in sorted(header["armies"].items()):
+        name = army.get("PlayerName", b"").decode(errors="replace")
+        last_tick = sim["players_last_tick"].get(source)
+        quit = f" (quit at {format_ticks(last_tick)})" if last_tick is not None else ""
+        print(f"  {name}{quit}")
+    if sim["desync_ticks"]:
+        print(
+            f"Desynced: {len(sim['desync_ticks'])} times, first at",
+            format_ticks(sim["desync_tick"])
+        )
+    else:
+        print("Desynced: no")
+
+
+def print_commands(args):
+    data = load_replay(args.replay)
+    names = args.name or commands.NAMES
+    parser = Parser(
+        commands=[getattr(commands, name) for name in names],
+        stop_on_desync=False,
+        limit=args.limit
+    )
+
+    # Only the commands that are printed are decoded
+    for tick, source, command in parser.parse_commands(data, json=True):
+        if args.source is not None and source not in args.source:
+            continue
+
+        command = json.loads(command)
+        print(json.dumps({"tick": tick, "source": source, **command}))
+
+
+def print_ticks(args):
+    data = load_replay(args.replay)
+    print(body_ticks(data[body_offset(data):]))
+
+
+def extract(args):
+    with open(args.replay, "rb") as f:
+        data = read_fafreplay(f, args.replay)
+
+    output = args.output
+    if output is None:
+        output = args.replay.rsplit(".", 1)[0] + ".scfareplay"
+
+    with open(output, "wb") as f:
+        f.write(data)
+
+
+def main(argv=None):
+    parser = argparse.ArgumentParser(
+        prog="fafreplay",
+        description="Inspect Supreme Commander Forged Alliance replay files"
+    )
+    subparsers = parser.add_subparsers(dest="command", required=True)
+
+    info = subparsers.add_parser(
+        "info",
+        help="show the map, players, duration and desyncs"
+    )
+    info.add_argument("replay")
+    info.set_defaults(func=print_info)
+
+    cmds = subparsers.add_parser(
+        "commands",
+        help="dump commands as JSON lines"
+    )
+    cmds.add_argument("replay")
+    cmds.add_argument(
+        "--name",
+        action="append",
+        choices=commands.NAMES,
+        help="only show commands with this name, may be repeated"
+    )
+    cmds.add_argument(
+        "--source",
+        action="append",
+        type=int,
+        help="only show commands from this command source, may be repeated"
+    )
+    cmds.add_argument(
+        "--limit",
+        type=int,
+        help="maximum number of commands with a matching name to parse"
+    )
+    cmds.set_defaults(func=print_commands)
+
+    ticks = subparsers.add_parser("ticks", help="print the number of ticks")
+    ticks.add_argument("replay")
+    ticks.set_defaults(func=print_ticks)
+
+    ext = subparsers.add_parser(
+        "extract",
+        help="convert a .fafreplay into a .scfareplay"
+    )
+    ext.add_argument("replay")
+    ext.add_argument(
+        "-o", "--output",
+        help="output path, defaults to the input path with a .scfareplay "
+        "extension"
+    )
+    ext.set_defaults(func=extract)
+
+    args = parser.parse_args(argv)
+    try:
+        args.func(args)
+    except (OSError, ReplayReadError, RuntimeError) as e:
+        print("Error:", e, file=sys.stderr)
+        return 2
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,25 @@
+import os
+
+
+def write_parquet(parser, data, directory):
+    """write_parquet(parser: Parser, data: bytes, directory: str) -> None
+
+    Parses a replay with `parser.parse_arrow` and writes the header and each
+    command type to separate Parquet files in `directory`. The files are named
+    `header.parquet` and `<command name>.parquet`. Requires pyarrow.
+    """
+    import pyarrow as pa
+    import pyarrow.parquet as pq
+
+    result = parser.parse_arrow(data)
+    os.makedirs(directory, exist_ok=True)
+
+    pq.write_table(
+        pa.Table.from_batches([result["header"].to_pyarrow()]),
+        os.path.join(directory, "header.parquet")
+    )
+    for name, batch in result["commands"].items():
+        pq.write_table(
+            pa.Table.from_batches([batch.to_pyarrow()]),
+            os.path.join(directory, f"{name}.parquet")
+        )
@@ -0,0 +1,429 @@
+"""Typed decoders for `LuaSimCallback` commands.
+
+`LuaSimCallback` commands carry a function name and a raw Lua table of
+arguments. This module keeps a registry mapping function names to decoders
+that turn those arguments into small dataclasses. Decoders for the well known
+FAF callbacks are registered by default, and custom decoders can be added with
+`register`.
+
+A decoder is called as `decoder(args, selection)` where `args` is the Lua
+table as returned by `Parser.parse` and `selection` is the list of selected
+unit ids. Callbacks whose arguments are not a table are never passed to a
+decoder. A decoder may return `None` to indicate that the callback wasn't
+understood.
+"""
+
+from dataclasses import dataclass
+from typing import Any, Callable, Dict, Iterator, List, Optional, Tuple
+
+from fafreplay._fafreplay import CommandType, Parser, commands
+
+Position = Tuple[float, float, float]
+
+DECODERS: Dict[str, Callable[[dict, List[int]], Any]] = {}
+
+
+def register(func, decoder=None):
+    """register(func: str, decoder: Optional[Callable] = None)
+
+    Registers `decoder` for callbacks named `func`, replacing any existing
+    decoder. Can also be used as a decorator:
+
+        @callbacks.register("MyModCallback")
+        def decode_my_mod(args, selection):
+            ...
+    """
+    if decoder is None:
+        def decorator(decoder):
+            DECODERS[func] = decoder
+            return decoder
+        return decorator
+
+    DECODERS[func] = decoder
+    return decoder
+
+
+def unregister(func):
+    """unregister(func: str) -> None
+
+    Removes the decoder for callbacks named `func` if there is one.
+    """
+    DECODERS.pop(func, None)
+
+
+def decode(command):
+    """decode(command: dict) -> Any
+
+    Decodes a `LuaSimCallback` command as returned by `Parser.parse`. Returns
+    `None` if there is no decoder registered for the callback or if its
+    arguments are not a table.
+    """
+    decoder = DECODERS.get(command["func"])
+    if decoder is None or not isinstance(command["args"], dict):
+        return None
+    return decoder(command["args"], command["selection"])
+
+
+@dataclass
+class Callback:
+    """A `LuaSimCallback` command together with when and by whom it was sent.
+    `value` is the decoded payload, or `None` if there is no decoder for
+    `func`."""
+    tick: int
+    source: int
+    func: str
+    args: Any
+    selection: List[int]
+    value: Any
+
+
+def iter_callbacks(data, parser=None) -> Iterator[Callback]:
+    """iter_callbacks(data: bytes, parser: Optional[Parser] = None) -> Iterator[Callback]
+
+    Parses a replay and decodes every `LuaSimCallback` command. A custom
+    parser needs to include `LuaSimCallback` in its commands. The ticks and
+    sources are tracked by `Parser.parse_commands`, so `Advance` and
+    `SetCommandSource` don't need to be included.
+    """
+    if parser is None:
+        parser = Parser(
+            commands=[commands.LuaSimCallback],
+            stop_on_desync=False
+        )
+
+    for tick, source, command in parser.parse_commands(data):
+        if command["name"] == CommandType.LuaSimCallback:
+            yield Callback(
+                tick=tick,
+                source=source,
+                func=command["func"],
+                args=command["args"],
+                selection=command["selection"],
+                value=decode(command),
+            )
+
+
+##########################
+# Lua conversion helpers #
+##########################
+
+
+def _str(value) -> Optional[str]:
+    if isinstance(value, bytes):
+        return value.decode(errors="replace")
+    return value
+
+
+def _int(value) -> Optional[int]:
+    if value is None:
+        return None
+    return int(float(value))
+
+
+def _position(table) -> Optional[Position]:
+    if not isinstance(table, dict):
+        return None
+    try:
+        return (table[1.0], table[2.0], table[3.0])
+    except KeyError:
+        return None
+
+
+####################
+# Builtin decoders #
+####################
+
+
+@dataclass
+class ResourceTransfer:
+    """Mass and energy sent to an ally. The amounts are the fraction of the
+    sender's stored resources, from 0 to 1. Armies are numbered from 1."""
+    from_army: int
+    to_army: int
+    mass: float
+    energy: float
+
+
+@dataclass
+class ChatMessage:
+    """A chat message logged to the sim. FAF sends these as a
+    `GiveResourcesToPlayer` callback without any resources."""
+    from_army: int
+    sender: str
+    text: str
+    to: str
+
+
+@dataclass
+class UnitTransfer:
+    """Units given to an ally. Armies are numbered from 1."""
+    from_army: int
+    to_army: int
+    units: List[int]
+
+
+@dataclass
+class Ping:
+    """A map ping. `type` is one of `alert`, `move`, `attack` or `marker`,
+    or the type of a special ping such as the warning shown when a nuke is
+    launched. `owner` is the army index starting from 0. Markers additionally
+    have a `name` and stay on the map until they are deleted."""
+    owner: int
+    type: str
+    position: Optional[Position]
+    lifetime: Optional[float]
+    name: Optional[str]
+
+
+@dataclass
+class MarkerUpdate:
+    """A change to a map marker. `action` is `delete`, `move` or `rename`,
+    or `renew` when all markers are sent to the UI again, in which case
+    `owner` and `id` are `None`."""
+    owner: Optional[int]
+    id: Optional[int]
+    action: str
+    position: Optional[Position]
+    name: Optional[str]
+
+
+@dataclass
+class RecallVote:
+    """A vote to recall, i.e. surrender as a team."""
+    from_army: int
+    vote: bool
+
+
+@dataclass
+class AbilityToggle:
+    """A unit ability turned on or off, e.g. automatic overcharge."""
+    ability: str
+    units: List[int]
+    enabled: bool
+
+
+@dataclass
+class TeamkillReport:
+    """A teamkill reported by the victim through the dialog that FAF shows
+    after being killed by an ally. Armies are numbered from 1 and
+    `game_time` is the time of the kill in seconds."""
+    victim: int
+    instigator: int
+    game_time: Optional[float]
+
+
+@dataclass
+class UnitOrder:
+    """A scripted order targeting another entity such as capping a mass
+    extractor with storages or assisting a unit."""
+    order: str
+    units: List[int]
+    target: Optional[int]
+
+
+@register("GiveResourcesToPlayer")
+def _resources(args, selection):
+    mass = args.get("Mass", 0.0)
+    energy = args.get("Energy", 0.0)
+    msg = args.get("Msg")
+    if not mass and not energy and isinstance(msg, dict):
+        return ChatMessage(
+            from_army=_int(args.get("From")),
+            sender=_str(args.get("Sender")),
+            text=_str(msg.get("text")),
+            to=_str(msg.get("to")),
+        )
+
+    return ResourceTransfer(
+        from_army=_int(args.get("From")),
+        to_army=_int(args.get("To")),
+        mass=mass,
+        energy=energy,
+    )
+
+
+@register("GiveUnitsToPlayer")
+def _units(args, selection):
+    return UnitTransfer(
+        from_army=_int(args.get("From")),
+        to_army=_int(args.get("To")),
+        units=list(selection),
+    )
+
+
+@register("SpawnPing")
+@register("SpawnSpecialPing")
+def _ping(args, selection):
+    return Ping(
+        owner=_int(args.get("Owner")),
+        type=_str(args.get("Type")),
+        position=_position(args.get("Location")),
+        lifetime=args.get("Lifetime"),
+        name=_str(args.get("Name")),
+    )
+
+
+@register("UpdateMarker")
+def _marker(args, selection):
+    action = _str(args.get("Action"))
+    if action == "renamed":
+        action = "rename"
+    return MarkerUpdate(
+        owner=_int(args.get("Owner")),
+        id=_int(args.get("ID")),
+        action=action,
+        position=_position(args.get("Location")),
+        name=_str(args.get("Name")),
+    )
+
+
+@register("SetRecallVote")
+def _recall(args, selection):
+    return RecallVote(
+        from_army=_int(args.get("From")),
+        vote=bool(args.get("Vote")),
+    )
+
+
+@register("AutoOvercharge")
+def _overcharge(args, selection):
+    return AbilityToggle(
+        ability="AutoOvercharge",
+        units=list(selection),
+        enabled=bool(args.get("auto")),
+    )
+
+
+@register("TeamkillReport")
+def _teamkill(args, selection):
+    return TeamkillReport(
+        victim=_int(args.get("victim")),
+        instigator=_int(args.get("instigator")),
+        game_time=args.get("gametime"),
+    )
+
+
+def _unit_order(order, key):
+    def decoder(args, selection):
+        return UnitOrder(
+            order=order,
+            units=list(selection),
+            target=_int(args.get(key)),
+        )
+    return decoder
+
+
+register("CapMex", _unit_order("CapMex", "target"))
+register("FlagShield", _unit_order("FlagShield", "target"))
+register("ValidateAssist", _unit_order("ValidateAssist", "target"))
+register("Rebuild", _unit_order("Rebuild", "entity"))
+
+
+############
+# Analyses #
+############
+
+
+@dataclass
+class Transfer:
+    """Resources or units given from one army to another. Armies are numbered
+    from 1, `source` is the command source that sent the callback. For
+    resource transfers `mass` and `energy` are the fraction of the giver's
+    stored resources and `units` is empty, for unit transfers `mass` and
+    `energy` are 0."""
+    tick: int
+    source: int
+    giver: int
+    receiver: int
+    mass: float
+    energy: float
+    units: List[int]
+
+    @property
+    def unit_count(self) -> int:
+        return len(self.units)
+
+
+def transfers(data, parser=None) -> List[Transfer]:
+    """transfers(data: bytes, parser: Optional[Parser] = None) -> List[Transfer]
+
+    Parses a replay and lists every mass, energy and unit gift between
+    players in the order they were sent. Chat messages, which FAF sends as
+    empty resource transfers, are not included.
+    """
+    result = []
+    for callback in iter_callbacks(data, parser):
+        value = callback.value
+        if isinstance(value, ResourceTransfer):
+            result.append(Transfer(
+                tick=callback.tick,
+                source=callback.source,
+                giver=value.from_army,
+                receiver=value.to_army,
+                mass=value.mass,
+                energy=value.energy,
+                units=[],
+            ))
+        elif isinstance(value, UnitTransfer):
+            result.append(Transfer(
+                tick=callback.tick,
+                source=callback.source,
+                giver=value.from_army,
+                receiver=value.to_army,
+                mass=0.0,
+                energy=0.0,
+                units=value.units,
+            ))
+    return result
+
+
+@dataclass
+class PingEvent:
+    """A map ping or a change to a map marker. `action` is `ping` for
+    alert, move and attack pings, `create` for new markers and `delete`,
+    `move` or `rename` for marker updates. `owner` is the army index starting
+    from 0 and `position` is `(x, y, z)` in map coordinates where `y` is the
+    height."""
+    tick: int
+    source: int
+    owner: int
+    type: str
+    action: str
+    position: Optional[Position]
+    name: Optional[str] = None
+    marker_id: Optional[int] = None
+
+
+def pings(data, parser=None) -> List[PingEvent]:
+    """pings(data: bytes, parser: Optional[Parser] = None) -> List[PingEvent]
+
+    Parses a replay and lists every map ping, including special pings, as
+    well as the creation and deletion of map markers in the order they were
+    sent. Marker `renew` updates are not included since they don't change
+    any marker.
+    """
+    result = []
+    for callback in iter_callbacks(data, parser):
+        value = callback.value
+        if isinstance(value, Ping):
+            result.append(PingEvent(
+                tick=callback.tick,
+                source=callback.source,
+                owner=value.owner,
+                type=value.type,
+                action="create" if value.type == "marker" else "ping",
+                position=value.position,
+                name=value.name,
+            ))
+        elif isinstance(value, MarkerUpdate) and value.action != "renew":
+            result.append(PingEvent(
+                tick=callback.tick,
+                source=callback.source,
+                owner=value.owner,
+                type="marker",
+                action=value.action,
+                position=value.position,
+                name=value.name,
+                marker_id=value.id,
+            ))
+    return result
@@ -0,0 +1,199 @@
+"""Standalone SVG images of replays.
+
+The images are plain SVG strings built without any third party dependencies,
+so they can be written straight to a file or embedded in HTML.
+"""
+
+from html import escape
+
+from fafreplay._fafreplay import (
+    CommandType,
+    Parser,
+    UnitCommandType,
+    commands,
+    pause_timeline,
+)
+
+# Colours are assigned to armies in order of their start spot
+PALETTE = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3",
+    "#ff7f00", "#ffd92f", "#a65628", "#f781bf",
+    "#66c2a5", "#8da0cb", "#e78ac3", "#a6d854",
+    "#b3b3b3", "#1b9e77", "#d95f02", "#7570b3",
+]
+
+# Commands counted as actions for the APM of the timeline
+ACTION_COMMANDS = [
+    commands.IssueCommand,
+    commands.IssueFactoryCommand,
+    commands.IncreaseCommandCount,
+    commands.DecreaseCommandCount,
+    commands.SetCommandTarget,
+    commands.SetCommandType,
+    commands.SetCommandCells,
+    commands.RemoveCommandFromQueue,
+]
+
+TICKS_PER_MINUTE = 600
+
+
+def _parse(data, extra_commands):
+    parser = Parser(
+        commands=[
+            commands.Advance,
+            commands.SetCommandSource,
+            *extra_commands,
+        ],
+        save_commands=True,
+        stop_on_desync=False
+    )
+    return parser.parse(data)
+
+
+def _colors(header):
+    """Map command sources to colours and names"""
+    players = [p for p in header["player_info"] if not p.civilian]
+    return {
+        p.source: (PALETTE[i % len(PALETTE)], p.name or p.army_name or "")
+        for i, p in enumerate(players)
+    }
+
+
+def _svg(width, height, elements):
+    return (
+        f'<svg xmlns="http://www.w3.org/2000/svg" width="{width}" '
+        f'height="{height}" viewBox="0 0 {width} {height}">'
+        + "".join(elements) +
+        "</svg>"
+    )
+
+
+def _legend(colors, x, y):
+    elements = []
+    for i, (color, name) in enumerate(colors.values()):
+        elements.append(
+            f'<rect x="{x}" y="{y + i * 14}" width="10" height="10" '
+            f'fill="{color}"/>'
+            f'<text x="{x + 14}" y="{y + i * 14 + 9}" font-size="10" '
+            f'font-family="sans-serif" fill="#ffffff">{escape(name)}</text>'
+        )
+    return elements
+
+
+def command_map(data, size=512):
+    """command_map(data: bytes, size: int = 512) -> str
+
+    Renders a square SVG of the map with every command position drawn as a
+    dot and every build location drawn as a square, coloured by army.
+    Positions are scaled from the map size in the header to `size` pixels.
+    """
+    replay = _parse(data, [
+        commands.IssueCommand,
+        commands.SetCommandTarget,
+        commands.SetCommandCells,
+    ])
+    header = replay["header"]
+    colors = _colors(header)
+
+    map_size = header["map_info"].size or (size, size)
+    scale = size / max(map_size)
+    elements = [f'<rect width="{size}" height="{size}" fill="#202020"/>']
+
+    source = 0
+    for command in replay["body"]["commands"]:
+        name = command["name"]
+        if name == CommandType.SetCommandSource:
+            source = command["id"]
+            continue
+        if name == CommandType.Advance or source not in colors:
+            continue
+
+        if name == CommandType.SetCommandCells:
+            position = command["position"]
+        else:
+            position = command["target"]
+        if not position or "x" not in position:
+            continue
+
+        color = colors[source][0]
+        x = position["x"] * scale
+        y = position["z"] * scale
+        if (
+            name == CommandType.IssueCommand and
+            command["type"] == UnitCommandType.BuildMobile
+        ):
+            elements.append(
+                f'<rect x="{x - 2:.1f}" y="{y - 2:.1f}" width="4" '
+                f'height="4" fill="none" stroke="{color}"/>'
+            )
+        else:
+            elements.append(
+                f'<circle cx="{x:.1f}" cy="{y:.1f}" r="1.5" '
+                f'fill="{color}" fill-opacity="0.5"/>'
+            )
+
+    elements.extend(_legend(colors, 6, 6))
+    return _svg(size, size, elements)
+
+
+def timeline(data, width=800, height=160):
+    """timeline(data: bytes, width: int = 800, height: int = 160) -> str
+
+    Renders an SVG strip over the length of the game showing the APM of each
+    army per minute as lines, pauses as shaded areas and the tick on which
+    each player's command source was terminated as a vertical marker.
+    """
+    replay = _parse(data, [*ACTION_COMMANDS, commands.CommandSourceTerminated])
+    header = replay["header"]
+    sim = replay["body"]["sim"]
+    colors = _colors(header)
+
+    total_ticks = max(sim["tick"], 1)
+    minutes = total_ticks // TICKS_PER_MINUTE + 1
+    actions = {source: [0] * minutes for source in colors}
+
+    tick = 0
+    source = 0
+    for command in replay["body"]["commands"]:
+        name = command["name"]
+        if name == CommandType.Advance:
+            tick += command["ticks"]
+        elif name == CommandType.SetCommandSource:
+            source = command["id"]
+        elif name != CommandType.CommandSourceTerminated and source in actions:
+            actions[source][tick // TICKS_PER_MINUTE] += 1
+
+    def x(tick):
+        return tick / total_ticks * width
+
+    elements = [f'<rect width="{width}" height="{height}" fill="#202020"/>']
+    for pause in pause_timeline(data):
+        end = pause.resume_tick if pause.resume_tick is not None else total_ticks
+        elements.append(
+            f'<rect x="{x(pause.tick):.1f}" y="0" '
+            f'width="{max(x(end) - x(pause.tick), 1):.1f}" height="{height}" '
+            f'fill="#ffffff" fill-opacity="0.2"/>'
+        )
+
+    peak = max((max(apm) for apm in actions.values()), default=0) or 1
+    for source, apm in actions.items():
+        points = " ".join(
+            f"{x(min((minute + 0.5) * TICKS_PER_MINUTE, total_ticks)):.1f},"
+            f"{height - count / peak * (height - 10):.1f}"
+            for minute, count in enumerate(apm)
+        )
+        elements.append(
+            f'<polyline points="{points}" fill="none" '
+            f'stroke="{colors[source][0]}" stroke-width="1.5"/>'
+        )
+
+    for source, last_tick in sim["players_last_tick"].items():
+        if source not in colors:
+            continue
+        elements.append(
+            f'<line x1="{x(last_tick):.1f}" y1="0" x2="{x(last_tick):.1f}" '
+            f'y2="{height}" stroke="{colors[source][0]}" '
+            f'stroke-dasharray="4 2"/>'
+        )
+
+    return _svg(width, height, elements)
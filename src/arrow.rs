@@ -0,0 +1,811 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyTuple};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::cdata;
+use crate::columns::command_id;
+use crate::fields::{visit_command, FieldVisitor};
+use crate::json::{armies_to_json, lua_to_json};
+use crate::map::MapInfo;
+use crate::mods::{mod_info, ModInfo};
+use crate::options::GameOptions;
+use crate::player::{player_info, PlayerInfo};
+use crate::teams::Teams;
+use faf_replay_parser::lua::LuaObject;
+use faf_replay_parser::scfa::replay::replay_command::NAMES;
+use faf_replay_parser::scfa::replay::{
+    Formation, Position, ReplayCommand, ReplayHeader, SimData, Target,
+};
+
+/// Fixed width values that can be stored in an arrow buffer.
+pub trait Primitive: Copy + Default {
+    /// Name of the pyarrow type factory function
+    const TYPE: &'static str;
+    /// Format string of the type in the arrow C data interface
+    const FORMAT: &'static str;
+
+    fn extend_le(self, buf: &mut Vec<u8>);
+}
+
+macro_rules! impl_primitive {
+    ($t:ty, $name:expr, $format:expr) => {
+        impl Primitive for $t {
+            const TYPE: &'static str = $name;
+            const FORMAT: &'static str = $format;
+
+            fn extend_le(self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    };
+}
+
+impl_primitive!(u8, "uint8", "C");
+impl_primitive!(u32, "uint32", "I");
+impl_primitive!(i32, "int32", "i");
+impl_primitive!(f32, "float32", "f");
+
+enum Values {
+    Fixed(&'static str, &'static str, Vec<u8>),
+    Boolean(Vec<bool>),
+    Variable(&'static str, Vec<i32>, Vec<u8>),
+    List(Vec<i32>, Box<Column>),
+    Struct(Vec<(&'static str, Column)>),
+}
+
+/// A nullable arrow array under construction. The buffers follow the arrow columnar format so
+/// they can be exported through the C data interface or handed to `pyarrow.Array.from_buffers`
+/// without converting individual values.
+pub struct Column {
+    validity: Vec<bool>,
+    values: Values,
+}
+
+impl Column {
+    fn with_values(values: Values) -> Column {
+        Column {
+            validity: Vec::new(),
+            values,
+        }
+    }
+
+    pub fn primitive<T: Primitive>() -> Column {
+        Column::with_values(Values::Fixed(T::TYPE, T::FORMAT, Vec::new()))
+    }
+
+    pub fn boolean() -> Column {
+        Column::with_values(Values::Boolean(Vec::new()))
+    }
+
+    pub fn string() -> Column {
+        Column::with_values(Values::Variable("string", vec![0], Vec::new()))
+    }
+
+    pub fn binary() -> Column {
+        Column::with_values(Values::Variable("binary", vec![0], Vec::new()))
+    }
+
+    pub fn list(values: Column) -> Column {
+        Column::with_values(Values::List(vec![0], Box::new(values)))
+    }
+
+    pub fn structure(fields: Vec<(&'static str, Column)>) -> Column {
+        Column::with_values(Values::Struct(fields))
+    }
+
+    pub fn push<T: Primitive>(&mut self, value: Option<T>) {
+        self.validity.push(value.is_some());
+        if let Values::Fixed(_, _, ref mut buf) = self.values {
+            value.unwrap_or_default().extend_le(buf);
+        } else {
+            unreachable!("column is not a primitive column")
+        }
+    }
+
+    pub fn push_bool(&mut self, value: Option<bool>) {
+        self.validity.push(value.is_some());
+        if let Values::Boolean(ref mut buf) = self.values {
+            buf.push(value.unwrap_or_default());
+        } else {
+            unreachable!("column is not a boolean column")
+        }
+    }
+
+    pub fn push_bytes(&mut self, value: Option<&[u8]>) {
+        self.validity.push(value.is_some());
+        if let Values::Variable(_, ref mut offsets, ref mut data) = self.values {
+            data.extend_from_slice(value.unwrap_or_default());
+            offsets.push(data.len() as i32);
+        } else {
+            unreachable!("column is not a variable width column")
+        }
+    }
+
+    pub fn push_str(&mut self, value: Option<&str>) {
+        self.push_bytes(value.map(str::as_bytes));
+    }
+
+    pub fn push_list<T: Primitive>(&mut self, value: Option<&[T]>) {
+        self.push_list_with(value.is_some(), |values| {
+            for item in value.unwrap_or_default() {
+                values.push(Some(*item));
+            }
+        });
+    }
+
+    /// Push a list entry by pushing its items to the values column in `push`.
+    pub fn push_list_with(&mut self, valid: bool, push: impl FnOnce(&mut Column)) {
+        self.validity.push(valid);
+        if let Values::List(ref mut offsets, ref mut values) = self.values {
+            push(values);
+            offsets.push(values.validity.len() as i32);
+        } else {
+            unreachable!("column is not a list column")
+        }
+    }
+
+    /// Mark the next struct entry as valid or null. Every field must be pushed to separately.
+    pub fn push_struct(&mut self, valid: bool) -> &mut [(&'static str, Column)] {
+        self.validity.push(valid);
+        if let Values::Struct(ref mut fields) = self.values {
+            fields
+        } else {
+            unreachable!("column is not a struct column")
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.validity.len()
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.validity.iter().filter(|valid| !**valid).count()
+    }
+
+    /// Format string of the type in the arrow C data interface
+    pub fn format(&self) -> &'static str {
+        match &self.values {
+            Values::Fixed(_, format, _) => format,
+            Values::Boolean(_) => "b",
+            Values::Variable("string", _, _) => "u",
+            Values::Variable(_, _, _) => "z",
+            Values::List(_, _) => "+l",
+            Values::Struct(_) => "+s",
+        }
+    }
+
+    /// The buffers of the array in the order of the arrow columnar format. The validity bitmap
+    /// is left out when there are no nulls.
+    pub fn buffers(&self) -> Vec<Option<Vec<u8>>> {
+        let validity = match self.null_count() {
+            0 => None,
+            _ => Some(bitmap(&self.validity)),
+        };
+
+        let mut buffers = vec![validity];
+        match &self.values {
+            Values::Fixed(_, _, data) => buffers.push(Some(data.clone())),
+            Values::Boolean(data) => buffers.push(Some(bitmap(data))),
+            Values::Variable(_, offsets, data) => {
+                buffers.push(Some(offsets_bytes(offsets)));
+                buffers.push(Some(data.clone()));
+            }
+            Values::List(offsets, _) => buffers.push(Some(offsets_bytes(offsets))),
+            Values::Struct(_) => {}
+        }
+        buffers
+    }
+
+    /// The child arrays of list and struct arrays with their field names.
+    pub fn children(&self) -> Vec<(&'static str, &Column)> {
+        match &self.values {
+            Values::List(_, values) => vec![("item", values)],
+            Values::Struct(fields) => fields.iter().map(|(name, c)| (*name, c)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn data_type<'p>(&self, pa: &'p PyModule) -> PyResult<&'p PyAny> {
+        match &self.values {
+            Values::Fixed(name, _, _) | Values::Variable(name, _, _) => pa.call_method0(name),
+            Values::Boolean(_) => pa.call_method0("bool_"),
+            Values::List(_, values) => pa.call_method1("list_", (values.data_type(pa)?,)),
+            Values::Struct(fields) => {
+                let mut types = Vec::with_capacity(fields.len());
+                for (name, column) in fields {
+                    types.push((*name, column.data_type(pa)?));
+                }
+                pa.call_method1("struct", (types,))
+            }
+        }
+    }
+
+    /// Convert to a `pyarrow.Array`.
+    pub fn to_pyarrow<'p>(&self, py: Python<'p>, pa: &'p PyModule) -> PyResult<&'p PyAny> {
+        let mut buffers = Vec::new();
+        for buf in self.buffers() {
+            buffers.push(match buf {
+                Some(data) => buffer(pa, &data)?.into_py(py),
+                None => py.None(),
+            });
+        }
+        let mut children = Vec::new();
+        for (_, column) in self.children() {
+            children.push(column.to_pyarrow(py, pa)?);
+        }
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("null_count", self.null_count())?;
+        if !children.is_empty() {
+            kwargs.set_item("children", children)?;
+        }
+        pa.getattr("Array")?.call_method(
+            "from_buffers",
+            (self.data_type(pa)?, self.len(), buffers),
+            Some(kwargs),
+        )
+    }
+}
+
+fn buffer<'p>(pa: &'p PyModule, data: &[u8]) -> PyResult<&'p PyAny> {
+    pa.call_method1("py_buffer", (PyBytes::new(pa.py(), data),))
+}
+
+fn bitmap(values: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; values.len().div_ceil(8)];
+    for (i, value) in values.iter().enumerate() {
+        if *value {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+fn offsets_bytes(offsets: &[i32]) -> Vec<u8> {
+    offsets.iter().flat_map(|o| o.to_le_bytes()).collect()
+}
+
+/// A set of named columns that make up a record batch.
+pub struct Batch {
+    names: Vec<&'static str>,
+    columns: Vec<Column>,
+}
+
+impl Batch {
+    pub fn new(fields: Vec<(&'static str, Column)>) -> Batch {
+        let (names, columns) = fields.into_iter().unzip();
+        Batch { names, columns }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.columns.first().map_or(0, Column::len)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &Column)> {
+        self.names.iter().copied().zip(self.columns.iter())
+    }
+
+    pub fn to_pyarrow<'p>(&self, py: Python<'p>, pa: &'p PyModule) -> PyResult<&'p PyAny> {
+        let arrays = PyList::empty(py);
+        for column in self.columns.iter() {
+            arrays.append(column.to_pyarrow(py, pa)?)?;
+        }
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("names", &self.names)?;
+        pa.getattr("RecordBatch")?
+            .call_method("from_arrays", PyTuple::new(py, [arrays]), Some(kwargs))
+    }
+}
+
+/// A record batch returned by `Parser.parse_arrow`. It implements the arrow PyCapsule interface,
+/// so it can be passed directly to libraries that support it such as pyarrow, polars or DuckDB.
+/// pyarrow is only needed for `to_pyarrow`.
+#[pyclass(module = "fafreplay")]
+pub struct ArrowBatch {
+    batch: Arc<Batch>,
+}
+
+#[pymethods]
+impl ArrowBatch {
+    #[getter]
+    fn num_rows(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    #[getter]
+    fn column_names(&self) -> Vec<&'static str> {
+        self.batch.names.clone()
+    }
+
+    /// Convert to a `pyarrow.RecordBatch`. Requires pyarrow.
+    fn to_pyarrow(&self, py: Python) -> PyResult<PyObject> {
+        let pa = py.import("pyarrow")?;
+        Ok(self.batch.to_pyarrow(py, pa)?.into_py(py))
+    }
+
+    /// Export the schema as an `arrow_schema` PyCapsule.
+    fn __arrow_c_schema__(&self, py: Python) -> PyResult<PyObject> {
+        cdata::schema_capsule(py, &self.batch)
+    }
+
+    /// Export the schema and data as a pair of `arrow_schema` and `arrow_array` PyCapsules. The
+    /// data is always exported with its own schema.
+    fn __arrow_c_array__(
+        &self,
+        py: Python,
+        requested_schema: Option<PyObject>,
+    ) -> PyResult<(PyObject, PyObject)> {
+        let _ = requested_schema;
+        Ok((
+            cdata::schema_capsule(py, &self.batch)?,
+            cdata::array_capsule(py, &self.batch)?,
+        ))
+    }
+
+    /// Export the batch as an `arrow_array_stream` PyCapsule containing a single batch. The data
+    /// is always exported with its own schema.
+    fn __arrow_c_stream__(
+        &self,
+        py: Python,
+        requested_schema: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let _ = requested_schema;
+        cdata::stream_capsule(py, self.batch.clone())
+    }
+
+    fn __len__(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ArrowBatch(num_rows={}, columns={:?})",
+            self.batch.num_rows(),
+            self.batch.names
+        )
+    }
+}
+
+impl ArrowBatch {
+    pub fn new(batch: Batch) -> ArrowBatch {
+        ArrowBatch {
+            batch: Arc::new(batch),
+        }
+    }
+}
+
+/// Builds one record batch per command type. The columns of each batch use the same names as the
+/// dictionaries returned by `Parser.parse`, with the addition of `tick` and `source`. Lua values
+/// (`cells`, `args` and `upgrades`) are stored as JSON strings and the `tick` of `VerifyChecksum`
+/// is renamed to `checksum_tick`.
+#[derive(Default)]
+pub struct CommandBatches {
+    batches: BTreeMap<u8, Batch>,
+}
+
+impl CommandBatches {
+    /// Add a row for `command`. The tick and source are taken from `sim` so this should be
+    /// called before the command is processed.
+    pub fn push(&mut self, sim: &SimData, command: &ReplayCommand) {
+        let batch = self.batches.entry(command_id(command)).or_insert_with(|| {
+            Batch::new(vec![
+                ("tick", Column::primitive::<u32>()),
+                ("source", Column::primitive::<u8>()),
+            ])
+        });
+        let first = batch.columns[0].validity.is_empty();
+        batch.columns[0].push(Some(sim.tick));
+        batch.columns[1].push(Some(sim.command_source));
+
+        visit_command(
+            command,
+            &mut Row {
+                batch,
+                first,
+                index: 2,
+            },
+        );
+    }
+
+    /// Convert to a dictionary of command names to `ArrowBatch`.
+    pub fn into_py(self, py: Python) -> PyResult<PyObject> {
+        let res = PyDict::new(py);
+
+        for (id, batch) in self.batches {
+            res.set_item(NAMES[id as usize], Py::new(py, ArrowBatch::new(batch))?)?;
+        }
+
+        Ok(res.into_py(py))
+    }
+}
+
+/// Pushes each field of a command to the next column of its batch. The columns are created
+/// while visiting the first command of each type.
+struct Row<'a> {
+    batch: &'a mut Batch,
+    first: bool,
+    index: usize,
+}
+
+impl Row<'_> {
+    fn column(&mut self, name: &'static str, new: impl FnOnce() -> Column) -> &mut Column {
+        if self.first {
+            // `tick` already holds the tick that the command was issued on
+            let name = match name {
+                "tick" => "checksum_tick",
+                name => name,
+            };
+            self.batch.names.push(name);
+            self.batch.columns.push(new());
+        }
+        self.index += 1;
+        &mut self.batch.columns[self.index - 1]
+    }
+}
+
+impl FieldVisitor for Row<'_> {
+    fn u8(&mut self, name: &'static str, value: u8) {
+        self.column(name, Column::primitive::<u8>).push(Some(value));
+    }
+
+    fn u32(&mut self, name: &'static str, value: u32) {
+        self.column(name, Column::primitive::<u32>)
+            .push(Some(value));
+    }
+
+    fn i32(&mut self, name: &'static str, value: i32) {
+        self.column(name, Column::primitive::<i32>)
+            .push(Some(value));
+    }
+
+    fn f32(&mut self, name: &'static str, value: f32) {
+        self.column(name, Column::primitive::<f32>)
+            .push(Some(value));
+    }
+
+    fn bool(&mut self, name: &'static str, value: Option<bool>) {
+        self.column(name, Column::boolean).push_bool(value);
+    }
+
+    fn string(&mut self, name: &'static str, value: &str) {
+        self.column(name, Column::string).push_str(Some(value));
+    }
+
+    fn bytes(&mut self, name: &'static str, value: &[u8]) {
+        self.column(name, Column::binary).push_bytes(Some(value));
+    }
+
+    fn ids(&mut self, name: &'static str, value: &[u32]) {
+        self.column(name, || Column::list(Column::primitive::<u32>()))
+            .push_list(Some(value));
+    }
+
+    fn position(&mut self, name: &'static str, value: &Position) {
+        push_position(self.column(name, position_column), Some(value));
+    }
+
+    fn target(&mut self, name: &'static str, value: &Target) {
+        push_target(self.column(name, target_column), value);
+    }
+
+    fn formation(&mut self, name: &'static str, value: Option<&Formation>) {
+        push_formation(self.column(name, formation_column), value);
+    }
+
+    fn lua(&mut self, name: &'static str, value: &LuaObject) {
+        push_json(self.column(name, Column::string), &lua_to_json(value));
+    }
+}
+
+fn position_column() -> Column {
+    Column::structure(vec![
+        ("x", Column::primitive::<f32>()),
+        ("y", Column::primitive::<f32>()),
+        ("z", Column::primitive::<f32>()),
+    ])
+}
+
+fn push_position(column: &mut Column, position: Option<&Position>) {
+    let fields = column.push_struct(position.is_some());
+    fields[0].1.push(position.map(|p| p.x));
+    fields[1].1.push(position.map(|p| p.y));
+    fields[2].1.push(position.map(|p| p.z));
+}
+
+/// Entity targets only have `id` set and position targets only have `x`, `y` and `z` set.
+fn target_column() -> Column {
+    Column::structure(vec![
+        ("id", Column::primitive::<u32>()),
+        ("x", Column::primitive::<f32>()),
+        ("y", Column::primitive::<f32>()),
+        ("z", Column::primitive::<f32>()),
+    ])
+}
+
+fn push_target(column: &mut Column, target: &Target) {
+    let (id, position) = match target {
+        Target::None => (None, None),
+        Target::Entity { id } => (Some(*id), None),
+        Target::Position(p) => (None, Some(p)),
+    };
+    let fields = column.push_struct(!matches!(target, Target::None));
+    fields[0].1.push(id);
+    fields[1].1.push(position.map(|p| p.x));
+    fields[2].1.push(position.map(|p| p.y));
+    fields[3].1.push(position.map(|p| p.z));
+}
+
+fn formation_column() -> Column {
+    Column::structure(vec![
+        ("a", Column::primitive::<f32>()),
+        ("b", Column::primitive::<f32>()),
+        ("c", Column::primitive::<f32>()),
+        ("d", Column::primitive::<f32>()),
+        ("scale", Column::primitive::<f32>()),
+    ])
+}
+
+fn push_formation(column: &mut Column, formation: Option<&Formation>) {
+    let fields = column.push_struct(formation.is_some());
+    fields[0].1.push(formation.map(|f| f.a));
+    fields[1].1.push(formation.map(|f| f.b));
+    fields[2].1.push(formation.map(|f| f.c));
+    fields[3].1.push(formation.map(|f| f.d));
+    fields[4].1.push(formation.map(|f| f.scale));
+}
+
+/// A single row batch with the header. The derived fields returned by `Parser.parse_header` are
+/// stored as structs, except for the `extra` game options which are part of `scenario`. The Lua
+/// tables `mods`, `scenario` and `armies` are stored as JSON strings, the same way as
+/// `Parser.parse_json` serializes them. Players are stored as a list of `{name, id}` structs.
+pub fn header_batch(header: &ReplayHeader, py: Python) -> Batch {
+    let players = player_info(&header.armies);
+    let teams = Teams::new(&players, &header.players);
+
+    let mut fields = vec![
+        ("scfa_version", Column::string()),
+        ("replay_version", Column::string()),
+        ("map_info", map_info_column()),
+        ("map_file", Column::string()),
+        ("mod_info", Column::list(mod_info_column())),
+        ("mods", Column::string()),
+        ("game_options", game_options_column()),
+        ("scenario", Column::string()),
+        (
+            "players",
+            Column::list(Column::structure(vec![
+                ("name", Column::string()),
+                ("id", Column::primitive::<u32>()),
+            ])),
+        ),
+        ("cheats_enabled", Column::boolean()),
+        ("army_count", Column::primitive::<u32>()),
+        ("teams", teams_column()),
+        ("player_info", Column::list(player_info_column())),
+        ("armies", Column::string()),
+        ("seed", Column::primitive::<u32>()),
+    ];
+
+    fields[0].1.push_str(Some(&header.scfa_version));
+    fields[1].1.push_str(Some(&header.replay_version));
+    push_map_info(
+        &mut fields[2].1,
+        &MapInfo::new(&header.map_file, &header.scenario),
+    );
+    fields[3].1.push_str(Some(&header.map_file));
+    fields[4].1.push_list_with(true, |values| {
+        for info in mod_info(&header.mods) {
+            push_mod_info(values, &info);
+        }
+    });
+    push_json(&mut fields[5].1, &lua_to_json(&header.mods));
+    push_game_options(
+        &mut fields[6].1,
+        &GameOptions::from_scenario(&header.scenario, py),
+    );
+    push_json(&mut fields[7].1, &lua_to_json(&header.scenario));
+    push_players(&mut fields[8].1, header);
+    fields[9].1.push_bool(Some(header.cheats_enabled));
+    fields[10].1.push(Some(header.army_count as u32));
+    push_teams(&mut fields[11].1, &teams);
+    fields[12].1.push_list_with(true, |values| {
+        for player in players.iter() {
+            push_player_info(values, player);
+        }
+    });
+    push_json(&mut fields[13].1, &armies_to_json(header));
+    fields[14].1.push(Some(header.seed));
+
+    Batch::new(fields)
+}
+
+fn push_json(column: &mut Column, value: &Value) {
+    column.push_str(Some(&value.to_string()));
+}
+
+fn push_players(column: &mut Column, header: &ReplayHeader) {
+    let mut players: Vec<(&String, &u32)> = header.players.iter().collect();
+    players.sort_by_key(|(_, id)| **id);
+
+    column.push_list_with(true, |values| {
+        for (name, id) in players {
+            let fields = values.push_struct(true);
+            fields[0].1.push_str(Some(name));
+            fields[1].1.push(Some(*id));
+        }
+    });
+}
+
+/// Same fields as `MapInfo`. Sizes are lists of `[width, height]` and start positions are sorted
+/// by name.
+fn map_info_column() -> Column {
+    Column::structure(vec![
+        ("map_file", Column::string()),
+        ("folder", Column::string()),
+        ("name", Column::string()),
+        ("version", Column::primitive::<u32>()),
+        ("size", Column::list(Column::primitive::<u32>())),
+        ("playable_area", Column::list(Column::primitive::<u32>())),
+        (
+            "start_positions",
+            Column::list(Column::structure(vec![
+                ("name", Column::string()),
+                ("x", Column::primitive::<f32>()),
+                ("y", Column::primitive::<f32>()),
+                ("z", Column::primitive::<f32>()),
+            ])),
+        ),
+    ])
+}
+
+fn push_map_info(column: &mut Column, info: &MapInfo) {
+    let fields = column.push_struct(true);
+    fields[0].1.push_str(Some(&info.map_file));
+    fields[1].1.push_str(info.folder.as_deref());
+    fields[2].1.push_str(info.name.as_deref());
+    fields[3].1.push(info.version);
+    let size = info.size.map(|(w, h)| [w, h]);
+    fields[4].1.push_list(size.as_ref().map(|s| &s[..]));
+    let area = info.playable_area.map(|(w, h)| [w, h]);
+    fields[5].1.push_list(area.as_ref().map(|s| &s[..]));
+
+    let positions = info.start_positions.as_ref().map(|positions| {
+        let mut positions: Vec<_> = positions.iter().collect();
+        positions.sort_by_key(|(name, _)| *name);
+        positions
+    });
+    fields[6].1.push_list_with(positions.is_some(), |values| {
+        for (name, (x, y, z)) in positions.unwrap_or_default() {
+            let fields = values.push_struct(true);
+            fields[0].1.push_str(Some(name));
+            fields[1].1.push(Some(*x));
+            fields[2].1.push(Some(*y));
+            fields[3].1.push(Some(*z));
+        }
+    });
+}
+
+/// Same fields as `ModInfo`
+fn mod_info_column() -> Column {
+    Column::structure(vec![
+        ("uid", Column::string()),
+        ("name", Column::string()),
+        ("version", Column::primitive::<f32>()),
+        ("author", Column::string()),
+        ("location", Column::string()),
+        ("ui_only", Column::boolean()),
+    ])
+}
+
+fn push_mod_info(column: &mut Column, info: &ModInfo) {
+    let fields = column.push_struct(true);
+    fields[0].1.push_str(Some(&info.uid));
+    fields[1].1.push_str(info.name.as_deref());
+    fields[2].1.push(info.version);
+    fields[3].1.push_str(info.author.as_deref());
+    fields[4].1.push_str(info.location.as_deref());
+    fields[5].1.push_bool(Some(info.ui_only));
+}
+
+/// Same fields as `GameOptions` except for `extra`
+fn game_options_column() -> Column {
+    Column::structure(vec![
+        ("victory", Column::string()),
+        ("share", Column::string()),
+        ("unit_cap", Column::primitive::<u32>()),
+        ("cheats_enabled", Column::boolean()),
+        ("cheat_mult", Column::primitive::<f32>()),
+        ("build_mult", Column::primitive::<f32>()),
+        ("timeouts", Column::primitive::<i32>()),
+        ("game_speed", Column::string()),
+        ("fog_of_war", Column::string()),
+        ("prebuilt_units", Column::boolean()),
+        ("no_rush", Column::primitive::<u32>()),
+        ("share_unit_cap", Column::string()),
+        ("team_lock", Column::boolean()),
+    ])
+}
+
+fn push_game_options(column: &mut Column, options: &GameOptions) {
+    let fields = column.push_struct(true);
+    fields[0].1.push_str(Some(&options.victory));
+    fields[1].1.push_str(Some(&options.share));
+    fields[2].1.push(Some(options.unit_cap));
+    fields[3].1.push_bool(Some(options.cheats_enabled));
+    fields[4].1.push(Some(options.cheat_mult));
+    fields[5].1.push(Some(options.build_mult));
+    fields[6].1.push(Some(options.timeouts));
+    fields[7].1.push_str(Some(&options.game_speed));
+    fields[8].1.push_str(Some(&options.fog_of_war));
+    fields[9].1.push_bool(Some(options.prebuilt_units));
+    fields[10].1.push(Some(options.no_rush));
+    fields[11].1.push_str(Some(&options.share_unit_cap));
+    fields[12].1.push_bool(Some(options.team_lock));
+}
+
+/// Same fields as `Teams`
+fn teams_column() -> Column {
+    Column::structure(vec![
+        (
+            "teams",
+            Column::list(Column::list(Column::primitive::<u32>())),
+        ),
+        ("observers", Column::list(Column::string())),
+    ])
+}
+
+fn push_teams(column: &mut Column, teams: &Teams) {
+    let fields = column.push_struct(true);
+    fields[0].1.push_list_with(true, |values| {
+        for team in teams.teams.iter() {
+            values.push_list(Some(&team[..]));
+        }
+    });
+    fields[1].1.push_list_with(true, |values| {
+        for name in teams.observers.iter() {
+            values.push_str(Some(name));
+        }
+    });
+}
+
+/// Same fields as `PlayerInfo`
+fn player_info_column() -> Column {
+    Column::structure(vec![
+        ("source", Column::primitive::<u32>()),
+        ("name", Column::string()),
+        ("army_name", Column::string()),
+        ("faction", Column::primitive::<u32>()),
+        ("team", Column::primitive::<u32>()),
+        ("start_spot", Column::primitive::<u32>()),
+        ("color", Column::primitive::<u32>()),
+        ("ai", Column::boolean()),
+        ("ai_personality", Column::string()),
+        ("civilian", Column::boolean()),
+        ("rating_mean", Column::primitive::<f32>()),
+        ("rating_deviation", Column::primitive::<f32>()),
+        ("clan", Column::string()),
+        ("country", Column::string()),
+        ("owner_id", Column::string()),
+    ])
+}
+
+fn push_player_info(column: &mut Column, player: &PlayerInfo) {
+    let fields = column.push_struct(true);
+    fields[0].1.push(Some(player.source));
+    fields[1].1.push_str(player.name.as_deref());
+    fields[2].1.push_str(player.army_name.as_deref());
+    fields[3].1.push(player.faction);
+    fields[4].1.push(player.team);
+    fields[5].1.push(player.start_spot);
+    fields[6].1.push(player.color);
+    fields[7].1.push_bool(Some(player.ai));
+    fields[8].1.push_str(player.ai_personality.as_deref());
+    fields[9].1.push_bool(Some(player.civilian));
+    fields[10].1.push(player.rating_mean);
+    fields[11].1.push(player.rating_deviation);
+    fields[12].1.push_str(player.clan.as_deref());
+    fields[13].1.push_str(player.country.as_deref());
+    fields[14].1.push_str(player.owner_id.as_deref());
+}
@@ -0,0 +1,93 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::cell::RefCell;
+
+use crate::columns::command_id;
+use crate::convert_result;
+use crate::replay::command_to_py;
+use crate::sim::process_command;
+
+use faf_replay_parser::scfa::replay::replay_command;
+use faf_replay_parser::scfa::ParserBuilder;
+
+/// Commands that can only be sent through the console or by cheating
+static AUDIT_COMMANDS: [u8; 5] = [
+    replay_command::CREATE_UNIT,
+    replay_command::DESTROY_ENTITY,
+    replay_command::WARP_ENTITY,
+    replay_command::DEBUG_COMMAND,
+    replay_command::EXECUTE_LUA_IN_SIM,
+];
+
+/// Console and cheat commands found in a replay
+#[pyclass(module = "fafreplay")]
+pub struct AuditReport {
+    /// `cheats_enabled` from the replay header
+    #[pyo3(get)]
+    pub cheats_enabled: bool,
+    /// The audited commands in the same format as `Parser.parse` returns them, with the
+    /// additional keys `tick` and `source`
+    #[pyo3(get)]
+    pub commands: Vec<PyObject>,
+}
+
+#[pymethods]
+impl AuditReport {
+    /// Whether cheat commands were used even though cheats were not enabled
+    #[getter]
+    fn flagged(&self) -> bool {
+        !self.cheats_enabled && !self.commands.is_empty()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AuditReport(cheats_enabled={}, commands={}, flagged={})",
+            if self.cheats_enabled { "True" } else { "False" },
+            self.commands.len(),
+            if self.flagged() { "True" } else { "False" }
+        )
+    }
+}
+
+/// audit_report(data: bytes) -> AuditReport
+///
+/// Parse a replay and collect every `CreateUnit`, `DestroyEntity`, `WarpEntity`,
+/// `DebugCommand` and `ExecuteLuaInSim` command. These are only available through the console
+/// or with cheats enabled.
+#[pyfunction]
+#[pyo3(text_signature = "(data)")]
+pub fn audit_report(py: Python, data: &PyBytes) -> PyResult<AuditReport> {
+    let mut bytes = data.as_bytes();
+    let mut commands = AUDIT_COMMANDS.to_vec();
+    commands.extend([replay_command::ADVANCE, replay_command::SET_COMMAND_SOURCE]);
+    let parser = ParserBuilder::new()
+        .commands(&commands)
+        .save_commands(false)
+        .stop_on_desync(false)
+        .build();
+
+    // Audited commands are rare, so they are converted in the callback while holding the GIL
+    let audited = RefCell::new(Vec::new());
+    let replay = parser.parse_with_callback(&mut bytes, |sim, command| {
+        if AUDIT_COMMANDS.contains(&command_id(command)) {
+            audited
+                .borrow_mut()
+                .push((sim.tick, sim.command_source, command_to_py(command, py)));
+        }
+        process_command(sim, command)
+    });
+    let replay = convert_result(replay)?;
+
+    let mut commands = Vec::new();
+    for (tick, source, obj) in audited.into_inner() {
+        let dict: &PyDict = obj.cast_as(py)?;
+        dict.set_item("tick", tick)?;
+        dict.set_item("source", source)?;
+        commands.push(obj);
+    }
+
+    Ok(AuditReport {
+        cheats_enabled: replay.header.cheats_enabled,
+        commands,
+    })
+}
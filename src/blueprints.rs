@@ -0,0 +1,325 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+
+use crate::player::{repr, FACTIONS};
+
+/// Names of blueprints that are numbered the same way for every faction, keyed by the id
+/// without the expansion and faction letters.
+#[rustfmt::skip]
+static COMMON_NAMES: [(&str, &str); 51] = [
+    ("l0001", "Armored Command Unit"),
+    ("l0101", "Land Scout"),
+    ("l0105", "T1 Engineer"),
+    ("l0208", "T2 Engineer"),
+    ("l0301", "Support Armored Command Unit"),
+    ("l0309", "T3 Engineer"),
+    ("a0101", "Air Scout"),
+    ("a0102", "Interceptor"),
+    ("a0103", "Attack Bomber"),
+    ("a0107", "Light Air Transport"),
+    ("a0302", "Spy Plane"),
+    ("a0303", "Air Superiority Fighter"),
+    ("a0304", "Strategic Bomber"),
+    ("b0101", "T1 Land Factory"),
+    ("b0102", "T1 Air Factory"),
+    ("b0103", "T1 Naval Factory"),
+    ("b0201", "T2 Land Factory HQ"),
+    ("b0202", "T2 Air Factory HQ"),
+    ("b0203", "T2 Naval Factory HQ"),
+    ("b0301", "T3 Land Factory HQ"),
+    ("b0302", "T3 Air Factory HQ"),
+    ("b0303", "T3 Naval Factory HQ"),
+    ("b1101", "T1 Power Generator"),
+    ("b1102", "Hydrocarbon Power Plant"),
+    ("b1103", "T1 Mass Extractor"),
+    ("b1104", "T2 Mass Fabricator"),
+    ("b1105", "Energy Storage"),
+    ("b1106", "Mass Storage"),
+    ("b1201", "T2 Power Generator"),
+    ("b1202", "T2 Mass Extractor"),
+    ("b1301", "T3 Power Generator"),
+    ("b1302", "T3 Mass Extractor"),
+    ("b1303", "T3 Mass Fabricator"),
+    ("b2101", "T1 Point Defense"),
+    ("b2104", "T1 Anti-Air Turret"),
+    ("b2108", "T2 Tactical Missile Launcher"),
+    ("b2109", "T1 Torpedo Launcher"),
+    ("b2204", "T2 Anti-Air Flak Artillery"),
+    ("b2301", "T2 Point Defense"),
+    ("b2302", "T3 Heavy Artillery Installation"),
+    ("b2303", "T2 Artillery Installation"),
+    ("b2304", "T3 Anti-Air SAM Launcher"),
+    ("b2305", "Strategic Missile Launcher"),
+    ("b3101", "T1 Radar System"),
+    ("b3102", "T1 Sonar System"),
+    ("b3104", "Omni Sensor Array"),
+    ("b3201", "T2 Radar System"),
+    ("b4201", "Tactical Missile Defense"),
+    ("b4302", "Strategic Missile Defense"),
+    ("b5101", "Wall Section"),
+    ("b5202", "Air Staging Facility"),
+];
+
+/// Names of individual units
+#[rustfmt::skip]
+static UNIT_NAMES: [(&str, &str); 102] = [
+    // UEF
+    ("uel0103", "Lobo"),
+    ("uel0104", "Archer"),
+    ("uel0106", "Mech Marine"),
+    ("uel0201", "Striker"),
+    ("uel0202", "Pillar"),
+    ("uel0203", "Riptide"),
+    ("uel0205", "Sky Boxer"),
+    ("uel0307", "Parashield"),
+    ("del0204", "Mongoose"),
+    ("uel0303", "Titan"),
+    ("uel0304", "Demolisher"),
+    ("xel0305", "Percival"),
+    ("xel0306", "Spearhead"),
+    ("uel0401", "Fatboy"),
+    ("uea0102", "Cyclone"),
+    ("uea0103", "Scorcher"),
+    ("uea0203", "Stinger"),
+    ("dea0202", "Janus"),
+    ("uea0303", "Wasp"),
+    ("uea0304", "Ambassador"),
+    ("uea0305", "Broadsword"),
+    ("ues0103", "Thunderhead"),
+    ("ues0201", "Valiant"),
+    ("ues0202", "Governor"),
+    ("ues0203", "Tigershark"),
+    ("ues0302", "Summit"),
+    ("xes0307", "Neptune"),
+    ("ues0401", "Atlantis"),
+    ("ueb2401", "Mavor"),
+    ("xeb2402", "Novax Center"),
+    // Aeon
+    ("ual0101", "Spirit"),
+    ("ual0103", "Fervor"),
+    ("ual0104", "Thistle"),
+    ("ual0106", "Flare"),
+    ("ual0111", "Evensong"),
+    ("ual0201", "Aurora"),
+    ("ual0202", "Obsidian"),
+    ("ual0205", "Ascendant"),
+    ("ual0307", "Asylum"),
+    ("xal0203", "Blaze"),
+    ("ual0303", "Harbinger Mark IV"),
+    ("ual0304", "Serenity"),
+    ("xal0305", "Sprite Striker"),
+    ("dal0310", "Absolver"),
+    ("ual0401", "Galactic Colossus"),
+    ("uaa0102", "Conservator"),
+    ("uaa0103", "Shimmer"),
+    ("uaa0203", "Specter"),
+    ("xaa0202", "Swift Wind"),
+    ("uaa0303", "Corona"),
+    ("uaa0304", "Shocker"),
+    ("uaa0310", "CZAR"),
+    ("uas0302", "Omen"),
+    ("uas0401", "Tempest"),
+    ("uab2302", "Emissary"),
+    ("xab1401", "Paragon"),
+    ("xab2307", "Salvation"),
+    // Cybran
+    ("url0101", "Mole"),
+    ("url0103", "Medusa"),
+    ("url0106", "Hunter"),
+    ("url0107", "Mantis"),
+    ("url0111", "Viper"),
+    ("url0202", "Rhino"),
+    ("url0203", "Wagner"),
+    ("url0205", "Banger"),
+    ("url0306", "Deceiver"),
+    ("drl0204", "Hoplite"),
+    ("url0303", "Loyalist"),
+    ("url0304", "Trebuchet"),
+    ("xrl0302", "Fire Beetle"),
+    ("xrl0305", "Brick"),
+    ("url0401", "Scathis"),
+    ("url0402", "Monkeylord"),
+    ("xrl0403", "Megalith"),
+    ("ura0102", "Prowler"),
+    ("ura0103", "Jester"),
+    ("ura0203", "Renegade"),
+    ("dra0202", "Corsair"),
+    ("ura0303", "Gemini"),
+    ("ura0304", "Revenant"),
+    ("ura0401", "Soul Ripper"),
+    ("urs0201", "Salem"),
+    ("urs0202", "Siren"),
+    ("urs0302", "Galaxy Class"),
+    // Seraphim
+    ("xsl0101", "Selen"),
+    ("xsl0103", "Zthuee"),
+    ("xsl0104", "Ia-istle"),
+    ("xsl0111", "Ythisah"),
+    ("xsl0201", "Thaam"),
+    ("xsl0202", "Ilshavoh"),
+    ("xsl0203", "Yenzyne"),
+    ("xsl0205", "Iashavoh"),
+    ("xsl0303", "Othuum"),
+    ("xsl0304", "Suthanus"),
+    ("xsl0305", "Usha-Ah"),
+    ("xsl0307", "Athanah"),
+    ("xsl0401", "Ythotha"),
+    ("xsa0102", "Ia-atha"),
+    ("xsa0103", "Sinnve"),
+    ("xsa0203", "Vulthoo"),
+    ("xsa0303", "Iazyne"),
+    ("xsa0304", "Sinntha"),
+];
+
+/// Information decoded from a unit blueprint id like `ueb0101`.
+///
+/// Unit ids are made up of an expansion letter, a faction letter, a category letter and four
+/// digits, the second of which is the tech level.
+#[pyclass(module = "fafreplay.blueprints")]
+pub struct BlueprintInfo {
+    /// The normalized (lowercase) id
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub faction: &'static str,
+    /// One of `land`, `air`, `naval`, `structure` or `civilian`
+    #[pyo3(get)]
+    pub category: Option<&'static str>,
+    /// Tech level from 1 to 4, where 4 means experimental. `None` for command units.
+    #[pyo3(get)]
+    pub tier: Option<u32>,
+    /// `Supreme Commander` or `Forged Alliance`
+    #[pyo3(get)]
+    pub expansion: Option<&'static str>,
+    /// Display name from the bundled table
+    #[pyo3(get)]
+    pub name: Option<&'static str>,
+}
+
+#[pymethods]
+impl BlueprintInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "BlueprintInfo(id={:?}, faction={:?}, category={}, tier={}, name={})",
+            self.id,
+            self.faction,
+            repr(&self.category.map(|s| format!("{:?}", s))),
+            repr(&self.tier),
+            repr(&self.name.map(|s| format!("{:?}", s)))
+        )
+    }
+}
+
+impl BlueprintInfo {
+    pub fn new(id: &str) -> Option<BlueprintInfo> {
+        let id = normalize(id);
+        let bytes = id.as_bytes();
+        if bytes.len() != 7 || !bytes[3..].iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+
+        let faction = match bytes[1] {
+            b'e' => FACTIONS[0],
+            b'a' => FACTIONS[1],
+            b'r' => FACTIONS[2],
+            b's' => FACTIONS[3],
+            _ => return None,
+        };
+        let category = match bytes[2] {
+            b'l' => Some("land"),
+            b'a' => Some("air"),
+            b's' => Some("naval"),
+            b'b' => Some("structure"),
+            b'c' => Some("civilian"),
+            _ => None,
+        };
+        let tier = match bytes[4] - b'0' {
+            tier @ 1..=4 => Some(tier as u32),
+            _ => None,
+        };
+        let expansion = match bytes[0] {
+            b'u' => Some("Supreme Commander"),
+            b'x' | b'd' => Some("Forged Alliance"),
+            _ => None,
+        };
+
+        Some(BlueprintInfo {
+            name: name(&id),
+            id,
+            faction,
+            category,
+            tier,
+            expansion,
+        })
+    }
+}
+
+/// Blueprints are referenced in a few different ways, e.g. `UEL0105`, `uel0105` or
+/// `/units/uel0105/uel0105_unit.bp`.
+fn normalize(id: &str) -> String {
+    let id = id.rsplit('/').next().unwrap_or(id);
+    let id = id.split('_').next().unwrap_or(id);
+    id.trim().to_lowercase()
+}
+
+/// Look up the display name of a normalized blueprint id
+fn name(id: &str) -> Option<&'static str> {
+    if let Some((_, name)) = UNIT_NAMES.iter().find(|(k, _)| *k == id) {
+        return Some(name);
+    }
+
+    // Seraphim were added in Forged Alliance so all of their ids start with `x`
+    if !matches!(id.get(..2)?, "ue" | "ua" | "ur" | "xs") {
+        return None;
+    }
+    COMMON_NAMES
+        .iter()
+        .find(|(k, _)| Some(*k) == id.get(2..))
+        .map(|(_, name)| *name)
+}
+
+pub fn add_blueprints(m: &PyModule) -> PyResult<()> {
+    /// Decoding of unit blueprint ids
+    #[pymodule]
+    fn blueprints(py: Python, m: &PyModule) -> PyResult<()> {
+        m.add_class::<BlueprintInfo>()?;
+        m.add_wrapped(wrap_pyfunction!(decode))?;
+        m.add_wrapped(wrap_pyfunction!(display_name))?;
+
+        let names = PyDict::new(py);
+        for (id, name) in UNIT_NAMES.iter() {
+            names.set_item(id, name)?;
+        }
+        for prefix in ["ue", "ua", "ur", "xs"] {
+            for (suffix, name) in COMMON_NAMES.iter() {
+                names.set_item(format!("{}{}", prefix, suffix), name)?;
+            }
+        }
+        m.add("NAMES", names)?;
+
+        Ok(())
+    }
+
+    m.add_wrapped(wrap_pymodule!(blueprints))?;
+
+    Ok(())
+}
+
+/// decode(id: str) -> Optional[BlueprintInfo]
+///
+/// Decode a unit blueprint id. Returns `None` if `id` doesn't look like a unit id.
+#[pyfunction]
+#[pyo3(text_signature = "(id)")]
+fn decode(id: &str) -> Option<BlueprintInfo> {
+    BlueprintInfo::new(id)
+}
+
+/// display_name(id: str) -> Optional[str]
+///
+/// Look up the display name of a unit blueprint id in the bundled table.
+#[pyfunction]
+#[pyo3(text_signature = "(id)")]
+fn display_name(id: &str) -> Option<&'static str> {
+    name(&normalize(id))
+}
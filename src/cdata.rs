@@ -0,0 +1,353 @@
+//! Export of record batches through the arrow C data interface and the PyCapsule interface.
+//! See https://arrow.apache.org/docs/format/CDataInterface.html and
+//! https://arrow.apache.org/docs/format/CDataInterface/PyCapsuleInterface.html
+use pyo3::ffi;
+use pyo3::prelude::*;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::sync::Arc;
+
+use crate::arrow::{Batch, Column};
+
+const ARROW_FLAG_NULLABLE: i64 = 2;
+
+#[repr(C)]
+pub struct ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut ArrowSchema,
+    dictionary: *mut ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut ArrowArray,
+    dictionary: *mut ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct ArrowArrayStream {
+    get_schema: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowSchema) -> c_int>,
+    get_next: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowArray) -> c_int>,
+    get_last_error: Option<unsafe extern "C" fn(*mut ArrowArrayStream) -> *const c_char>,
+    release: Option<unsafe extern "C" fn(*mut ArrowArrayStream)>,
+    private_data: *mut c_void,
+}
+
+/// Owned by an exported `ArrowSchema` until it is released.
+struct SchemaData {
+    format: CString,
+    name: CString,
+    children: Vec<*mut ArrowSchema>,
+}
+
+/// Owned by an exported `ArrowArray` until it is released.
+struct ArrayData {
+    // Stored as `u64` so that every buffer is 8 byte aligned
+    buffers: Vec<Option<Vec<u64>>>,
+    pointers: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+}
+
+/// Owned by an exported `ArrowArrayStream` until it is released.
+struct StreamData {
+    batch: Arc<Batch>,
+    done: bool,
+}
+
+fn schema(format: &str, name: &str, flags: i64, children: Vec<ArrowSchema>) -> ArrowSchema {
+    let mut data = Box::new(SchemaData {
+        format: CString::new(format).unwrap(),
+        name: CString::new(name).unwrap(),
+        children: children
+            .into_iter()
+            .map(|child| Box::into_raw(Box::new(child)))
+            .collect(),
+    });
+
+    ArrowSchema {
+        format: data.format.as_ptr(),
+        name: data.name.as_ptr(),
+        metadata: ptr::null(),
+        flags,
+        n_children: data.children.len() as i64,
+        children: data.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_schema),
+        private_data: Box::into_raw(data) as *mut c_void,
+    }
+}
+
+fn column_schema(name: &str, column: &Column) -> ArrowSchema {
+    let children = column
+        .children()
+        .into_iter()
+        .map(|(name, child)| column_schema(name, child))
+        .collect();
+
+    schema(column.format(), name, ARROW_FLAG_NULLABLE, children)
+}
+
+/// Record batches are exported as a struct array without nulls.
+fn batch_schema(batch: &Batch) -> ArrowSchema {
+    let children = batch
+        .fields()
+        .map(|(name, column)| column_schema(name, column))
+        .collect();
+
+    schema("+s", "", 0, children)
+}
+
+unsafe extern "C" fn release_schema(schema: *mut ArrowSchema) {
+    if schema.is_null() || (*schema).release.is_none() {
+        return;
+    }
+    let data = Box::from_raw((*schema).private_data as *mut SchemaData);
+    for child in data.children {
+        // Children may have been moved out by the consumer
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    (*schema).release = None;
+}
+
+fn array(
+    length: usize,
+    null_count: usize,
+    buffers: Vec<Option<Vec<u8>>>,
+    children: Vec<ArrowArray>,
+) -> ArrowArray {
+    let mut data = Box::new(ArrayData {
+        buffers: buffers
+            .iter()
+            .map(|buf| buf.as_deref().map(aligned))
+            .collect(),
+        pointers: Vec::new(),
+        children: children
+            .into_iter()
+            .map(|child| Box::into_raw(Box::new(child)))
+            .collect(),
+    });
+    // Missing buffers are null pointers
+    data.pointers = data
+        .buffers
+        .iter()
+        .map(|buf| {
+            buf.as_ref()
+                .map_or(ptr::null(), |buf| buf.as_ptr() as *const c_void)
+        })
+        .collect();
+
+    ArrowArray {
+        length: length as i64,
+        null_count: null_count as i64,
+        offset: 0,
+        n_buffers: data.pointers.len() as i64,
+        n_children: data.children.len() as i64,
+        buffers: data.pointers.as_mut_ptr(),
+        children: data.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_array),
+        private_data: Box::into_raw(data) as *mut c_void,
+    }
+}
+
+fn aligned(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_ne_bytes(word)
+        })
+        .collect()
+}
+
+fn column_array(column: &Column) -> ArrowArray {
+    let children = column
+        .children()
+        .into_iter()
+        .map(|(_, child)| column_array(child))
+        .collect();
+
+    array(
+        column.len(),
+        column.null_count(),
+        column.buffers(),
+        children,
+    )
+}
+
+fn batch_array(batch: &Batch) -> ArrowArray {
+    let children = batch
+        .fields()
+        .map(|(_, column)| column_array(column))
+        .collect();
+
+    array(batch.num_rows(), 0, vec![None], children)
+}
+
+unsafe extern "C" fn release_array(array: *mut ArrowArray) {
+    if array.is_null() || (*array).release.is_none() {
+        return;
+    }
+    let data = Box::from_raw((*array).private_data as *mut ArrayData);
+    for child in data.children {
+        // Children may have been moved out by the consumer
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    (*array).release = None;
+}
+
+/// A released array marks the end of a stream.
+fn end_of_stream() -> ArrowArray {
+    ArrowArray {
+        length: 0,
+        null_count: 0,
+        offset: 0,
+        n_buffers: 0,
+        n_children: 0,
+        buffers: ptr::null_mut(),
+        children: ptr::null_mut(),
+        dictionary: ptr::null_mut(),
+        release: None,
+        private_data: ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn stream_get_schema(
+    stream: *mut ArrowArrayStream,
+    out: *mut ArrowSchema,
+) -> c_int {
+    let data = &*((*stream).private_data as *const StreamData);
+    ptr::write(out, batch_schema(&data.batch));
+    0
+}
+
+unsafe extern "C" fn stream_get_next(stream: *mut ArrowArrayStream, out: *mut ArrowArray) -> c_int {
+    let data = &mut *((*stream).private_data as *mut StreamData);
+    if data.done {
+        ptr::write(out, end_of_stream());
+    } else {
+        ptr::write(out, batch_array(&data.batch));
+        data.done = true;
+    }
+    0
+}
+
+unsafe extern "C" fn stream_get_last_error(_stream: *mut ArrowArrayStream) -> *const c_char {
+    ptr::null()
+}
+
+unsafe extern "C" fn release_stream(stream: *mut ArrowArrayStream) {
+    if stream.is_null() || (*stream).release.is_none() {
+        return;
+    }
+    drop(Box::from_raw((*stream).private_data as *mut StreamData));
+    (*stream).release = None;
+}
+
+/// A C data interface struct that can be stored in a PyCapsule.
+trait Exported {
+    /// Capsule name required by the PyCapsule interface
+    const NAME: &'static [u8];
+
+    fn release(&mut self);
+}
+
+impl Exported for ArrowSchema {
+    const NAME: &'static [u8] = b"arrow_schema\0";
+
+    fn release(&mut self) {
+        if let Some(release) = self.release {
+            unsafe { release(self) }
+        }
+    }
+}
+
+impl Exported for ArrowArray {
+    const NAME: &'static [u8] = b"arrow_array\0";
+
+    fn release(&mut self) {
+        if let Some(release) = self.release {
+            unsafe { release(self) }
+        }
+    }
+}
+
+impl Exported for ArrowArrayStream {
+    const NAME: &'static [u8] = b"arrow_array_stream\0";
+
+    fn release(&mut self) {
+        if let Some(release) = self.release {
+            unsafe { release(self) }
+        }
+    }
+}
+
+/// Consumers that import the struct set its `release` to null, otherwise it is released here.
+unsafe extern "C" fn drop_capsule<T: Exported>(capsule: *mut ffi::PyObject) {
+    let value = ffi::PyCapsule_GetPointer(capsule, T::NAME.as_ptr() as *const c_char) as *mut T;
+    if value.is_null() {
+        ffi::PyErr_Clear();
+        return;
+    }
+    let mut value = Box::from_raw(value);
+    value.release();
+}
+
+fn capsule<T: Exported>(py: Python, value: T) -> PyResult<PyObject> {
+    let value = Box::into_raw(Box::new(value));
+    unsafe {
+        let capsule = ffi::PyCapsule_New(
+            value as *mut c_void,
+            T::NAME.as_ptr() as *const c_char,
+            Some(drop_capsule::<T>),
+        );
+        if capsule.is_null() {
+            Box::from_raw(value).release();
+        }
+        PyObject::from_owned_ptr_or_err(py, capsule)
+    }
+}
+
+pub fn schema_capsule(py: Python, batch: &Batch) -> PyResult<PyObject> {
+    capsule(py, batch_schema(batch))
+}
+
+pub fn array_capsule(py: Python, batch: &Batch) -> PyResult<PyObject> {
+    capsule(py, batch_array(batch))
+}
+
+pub fn stream_capsule(py: Python, batch: Arc<Batch>) -> PyResult<PyObject> {
+    let data = Box::new(StreamData { batch, done: false });
+    capsule(
+        py,
+        ArrowArrayStream {
+            get_schema: Some(stream_get_schema),
+            get_next: Some(stream_get_next),
+            get_last_error: Some(stream_get_last_error),
+            release: Some(release_stream),
+            private_data: Box::into_raw(data) as *mut c_void,
+        },
+    )
+}
@@ -0,0 +1,195 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::collections::HashMap;
+
+use crate::fields::{visit_command, FieldVisitor};
+use crate::replay;
+use faf_replay_parser::lua::LuaObject;
+use faf_replay_parser::scfa::replay::{Formation, Position, ReplayCommand, SimData, Target};
+
+/// Command data stored as one `Vec` per field. This is much cheaper to hand over to python than
+/// a list of dictionaries since each column can be copied as a single buffer.
+#[derive(Default)]
+pub struct CommandColumns {
+    pub tick: Vec<u32>,
+    pub source: Vec<u8>,
+    pub command: Vec<u8>,
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+    pub target: Vec<i64>,
+    pub blueprint: Vec<i32>,
+    /// Unique blueprint names referenced by the `blueprint` column
+    pub blueprints: Vec<String>,
+    blueprint_index: HashMap<String, i32>,
+}
+
+impl CommandColumns {
+    /// Add a row for `command`. The tick and source are taken from `sim` so this should be
+    /// called before the command is processed.
+    pub fn push(&mut self, sim: &SimData, command: &ReplayCommand) {
+        let mut fields = Fields {
+            columns: self,
+            position: (f32::NAN, f32::NAN, f32::NAN),
+            target: -1,
+            blueprint: -1,
+        };
+        visit_command(command, &mut fields);
+        let Fields {
+            position,
+            target,
+            blueprint,
+            ..
+        } = fields;
+
+        self.tick.push(sim.tick);
+        self.source.push(sim.command_source);
+        self.command.push(command_id(command));
+        self.x.push(position.0);
+        self.y.push(position.1);
+        self.z.push(position.2);
+        self.target.push(target);
+        self.blueprint.push(blueprint);
+    }
+
+    fn blueprint_index(&mut self, blueprint: &str) -> i32 {
+        if let Some(index) = self.blueprint_index.get(blueprint) {
+            return *index;
+        }
+        let index = self.blueprints.len() as i32;
+        self.blueprints.push(blueprint.to_string());
+        self.blueprint_index.insert(blueprint.to_string(), index);
+        index
+    }
+
+    fn len(&self) -> usize {
+        self.tick.len()
+    }
+
+    /// Convert the columns to a numpy structured array. Requires numpy to be installed.
+    pub fn to_numpy(&self, py: Python) -> PyResult<PyObject> {
+        let numpy = py.import("numpy")?;
+        let columns = [
+            ("tick", "<u4", le_bytes(self.tick.iter().map(|v| v.to_le_bytes()))),
+            ("source", "u1", self.source.clone()),
+            ("command", "u1", self.command.clone()),
+            ("x", "<f4", le_bytes(self.x.iter().map(|v| v.to_le_bytes()))),
+            ("y", "<f4", le_bytes(self.y.iter().map(|v| v.to_le_bytes()))),
+            ("z", "<f4", le_bytes(self.z.iter().map(|v| v.to_le_bytes()))),
+            ("target", "<i8", le_bytes(self.target.iter().map(|v| v.to_le_bytes()))),
+            ("blueprint", "<i4", le_bytes(self.blueprint.iter().map(|v| v.to_le_bytes()))),
+        ];
+        let dtype: Vec<(&str, &str)> = columns.iter().map(|(name, dtype, _)| (*name, *dtype)).collect();
+
+        let array = numpy.call_method1("empty", (self.len(), dtype))?;
+        for (name, dtype, bytes) in columns.iter() {
+            let column = numpy.call_method1("frombuffer", (PyBytes::new(py, bytes), *dtype))?;
+            array.set_item(*name, column)?;
+        }
+
+        Ok(array.into_py(py))
+    }
+}
+
+/// Like `ReplayBody::into_py` but with the commands converted to a numpy array.
+pub fn columns_into_py(sim: SimData, columns: CommandColumns, py: Python) -> PyResult<PyObject> {
+    let res = PyDict::new(py);
+
+    res.set_item::<&str, PyObject>("sim", replay::SimData(sim).into_py(py))?;
+    res.set_item("commands", columns.to_numpy(py)?)?;
+    res.set_item("blueprints", columns.blueprints)?;
+
+    Ok(res.into_py(py))
+}
+
+/// Picks the position, target entity and blueprint out of the fields of a command. `CreateUnit`
+/// only has an `x` and `z` position.
+struct Fields<'a> {
+    columns: &'a mut CommandColumns,
+    position: (f32, f32, f32),
+    target: i64,
+    blueprint: i32,
+}
+
+impl FieldVisitor for Fields<'_> {
+    fn u8(&mut self, _name: &'static str, _value: u8) {}
+
+    fn u32(&mut self, _name: &'static str, _value: u32) {}
+
+    fn i32(&mut self, _name: &'static str, _value: i32) {}
+
+    fn f32(&mut self, name: &'static str, value: f32) {
+        match name {
+            "x" => self.position.0 = value,
+            "y" => self.position.1 = value,
+            "z" => self.position.2 = value,
+            _ => (),
+        }
+    }
+
+    fn bool(&mut self, _name: &'static str, _value: Option<bool>) {}
+
+    fn string(&mut self, name: &'static str, value: &str) {
+        // Game commands have an empty blueprint unless they build something
+        if name == "blueprint" && !value.is_empty() {
+            self.blueprint = self.columns.blueprint_index(value);
+        }
+    }
+
+    fn bytes(&mut self, _name: &'static str, _value: &[u8]) {}
+
+    fn ids(&mut self, _name: &'static str, _value: &[u32]) {}
+
+    fn position(&mut self, _name: &'static str, value: &Position) {
+        self.position = (value.x, value.y, value.z);
+    }
+
+    fn target(&mut self, _name: &'static str, value: &Target) {
+        match value {
+            Target::None => (),
+            Target::Entity { id } => self.target = *id as i64,
+            Target::Position(p) => self.position = (p.x, p.y, p.z),
+        }
+    }
+
+    fn formation(&mut self, _name: &'static str, _value: Option<&Formation>) {}
+
+    fn lua(&mut self, _name: &'static str, _value: &LuaObject) {}
+}
+
+fn le_bytes<const N: usize>(iter: impl Iterator<Item = [u8; N]>) -> Vec<u8> {
+    iter.flatten().collect()
+}
+
+/// Get the id from `replay_command` that corresponds to the enum variant.
+pub fn command_id(command: &ReplayCommand) -> u8 {
+    use faf_replay_parser::scfa::replay::replay_command::*;
+    use ReplayCommand::*;
+
+    match command {
+        Advance { .. } => ADVANCE,
+        SetCommandSource { .. } => SET_COMMAND_SOURCE,
+        CommandSourceTerminated => COMMAND_SOURCE_TERMINATED,
+        VerifyChecksum { .. } => VERIFY_CHECKSUM,
+        RequestPause => REQUEST_PAUSE,
+        Resume => RESUME,
+        SingleStep => SINGLE_STEP,
+        CreateUnit { .. } => CREATE_UNIT,
+        CreateProp { .. } => CREATE_PROP,
+        DestroyEntity { .. } => DESTROY_ENTITY,
+        WarpEntity { .. } => WARP_ENTITY,
+        ProcessInfoPair { .. } => PROCESS_INFO_PAIR,
+        IssueCommand(_) => ISSUE_COMMAND,
+        IssueFactoryCommand(_) => ISSUE_FACTORY_COMMAND,
+        IncreaseCommandCount { .. } => INCREASE_COMMAND_COUNT,
+        DecreaseCommandCount { .. } => DECREASE_COMMAND_COUNT,
+        SetCommandTarget { .. } => SET_COMMAND_TARGET,
+        SetCommandType { .. } => SET_COMMAND_TYPE,
+        SetCommandCells { .. } => SET_COMMAND_CELLS,
+        RemoveCommandFromQueue { .. } => REMOVE_COMMAND_FROM_QUEUE,
+        DebugCommand { .. } => DEBUG_COMMAND,
+        ExecuteLuaInSim { .. } => EXECUTE_LUA_IN_SIM,
+        LuaSimCallback { .. } => LUA_SIM_CALLBACK,
+        EndGame => END_GAME,
+    }
+}
@@ -1,7 +1,133 @@
-use faf_replay_parser::scfa::replay::replay_command;
+use faf_replay_parser::scfa::replay::{game_command, replay_command};
+use pyo3::once_cell::GILOnceCell;
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
 use pyo3::wrap_pymodule;
 
+/// Names of the unit command types as used by the engine (`UNITCOMMAND_<Name>`)
+pub static UNIT_COMMAND_NAMES: [&str; game_command::MAX as usize + 1] = [
+    "None",
+    "Stop",
+    "Move",
+    "Dive",
+    "FormMove",
+    "BuildSiloTactical",
+    "BuildSiloNuke",
+    "BuildFactory",
+    "BuildMobile",
+    "BuildAssist",
+    "Attack",
+    "FormAttack",
+    "Nuke",
+    "Tactical",
+    "Teleport",
+    "Guard",
+    "Patrol",
+    "Ferry",
+    "FormPatrol",
+    "Reclaim",
+    "Repair",
+    "Capture",
+    "TransportLoadUnits",
+    "TransportReverseLoadUnits",
+    "TransportUnloadUnits",
+    "TransportUnloadSpecificUnits",
+    "DetachFromTransport",
+    "Upgrade",
+    "Script",
+    "AssistCommander",
+    "KillSelf",
+    "DestroySelf",
+    "Sacrifice",
+    "Pause",
+    "OverCharge",
+    "AggressiveMove",
+    "FormAggressiveMove",
+    "AssistMove",
+    "SpecialAction",
+    "Dock",
+];
+
+/// Names of the target types of a `GameCommand`
+pub static TARGET_TYPE_NAMES: [&str; 3] = ["None", "Entity", "Position"];
+
+/// Names of the type markers of serialized Lua objects
+pub static LUA_TYPE_NAMES: [&str; 6] = ["Float", "String", "Nil", "Bool", "Table", "TableEnd"];
+
+/// A python `IntEnum` class along with its members, so converting a value doesn't need to go
+/// through the (slow) enum constructor.
+pub struct IntEnum {
+    pub class: PyObject,
+    members: Vec<PyObject>,
+}
+
+impl IntEnum {
+    /// Create an `IntEnum` with members numbered from 0 in the order of `names`
+    fn new(py: Python, name: &str, names: &[&str]) -> PyResult<IntEnum> {
+        let items = PyList::empty(py);
+        for (value, member) in names.iter().enumerate() {
+            items.append((*member, value))?;
+        }
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("module", "fafreplay")?;
+
+        let class = py
+            .import("enum")?
+            .getattr("IntEnum")?
+            .call((name, items), Some(kwargs))?;
+        let members = (0..names.len())
+            .map(|value| class.call1((value,)).map(|m| m.into_py(py)))
+            .collect::<PyResult<_>>()?;
+
+        Ok(IntEnum {
+            class: class.into_py(py),
+            members,
+        })
+    }
+
+    /// Convert a value to the matching enum member. Unknown values are left as integers.
+    pub fn member(&self, value: u8, py: Python) -> PyObject {
+        match self.members.get(value as usize) {
+            Some(member) => member.clone_ref(py),
+            None => value.into_py(py),
+        }
+    }
+}
+
+static COMMAND_TYPE: GILOnceCell<IntEnum> = GILOnceCell::new();
+static UNIT_COMMAND_TYPE: GILOnceCell<IntEnum> = GILOnceCell::new();
+static TARGET_TYPE: GILOnceCell<IntEnum> = GILOnceCell::new();
+static LUA_TYPE: GILOnceCell<IntEnum> = GILOnceCell::new();
+
+fn cached_enum(
+    cell: &'static GILOnceCell<IntEnum>,
+    py: Python,
+    name: &str,
+    names: &[&str],
+) -> &'static IntEnum {
+    cell.get_or_init(py, || IntEnum::new(py, name, names).expect("enum can be created"))
+}
+
+/// The `CommandType` enum of replay command ids
+pub fn command_type(py: Python) -> &'static IntEnum {
+    cached_enum(&COMMAND_TYPE, py, "CommandType", &replay_command::NAMES)
+}
+
+/// The `UnitCommandType` enum of `GameCommand` types
+pub fn unit_command_type(py: Python) -> &'static IntEnum {
+    cached_enum(&UNIT_COMMAND_TYPE, py, "UnitCommandType", &UNIT_COMMAND_NAMES)
+}
+
+/// The `TargetType` enum of `GameCommand` target types
+pub fn target_type(py: Python) -> &'static IntEnum {
+    cached_enum(&TARGET_TYPE, py, "TargetType", &TARGET_TYPE_NAMES)
+}
+
+/// The `LuaType` enum of Lua object type markers
+pub fn lua_type(py: Python) -> &'static IntEnum {
+    cached_enum(&LUA_TYPE, py, "LuaType", &LUA_TYPE_NAMES)
+}
+
 #[rustfmt::skip]
 pub fn add_constants(m: &PyModule) -> PyResult<()> {
     #[pymodule]
@@ -39,7 +165,25 @@ pub fn add_constants(m: &PyModule) -> PyResult<()> {
         Ok(())
     }
 
+    #[pymodule]
+    fn unit_commands(_py: Python, m: &PyModule) -> PyResult<()> {
+        for (value, name) in UNIT_COMMAND_NAMES.iter().enumerate() {
+            m.add(name, value as u8)?;
+        }
+        m.add("MAX", game_command::MAX)?;
+
+        m.add("NAMES", UNIT_COMMAND_NAMES)?;
+
+        Ok(())
+    }
+
     m.add_wrapped(wrap_pymodule!(commands))?;
+    m.add_wrapped(wrap_pymodule!(unit_commands))?;
+    let py = m.py();
+    m.add("CommandType", &command_type(py).class)?;
+    m.add("UnitCommandType", &unit_command_type(py).class)?;
+    m.add("TargetType", &target_type(py).class)?;
+    m.add("LuaType", &lua_type(py).class)?;
 
     Ok(())
 }
@@ -0,0 +1,141 @@
+use faf_replay_parser::lua::LuaObject;
+use faf_replay_parser::scfa::replay::{Formation, GameCommand, Position, ReplayCommand, Target};
+
+/// Receives the fields of a command in the order they appear in the dictionaries returned by
+/// `Parser.parse`. Every serializer goes through `visit_command` so the field names and types
+/// only have to be kept up to date in one place.
+pub trait FieldVisitor {
+    fn u8(&mut self, name: &'static str, value: u8);
+    fn u32(&mut self, name: &'static str, value: u32);
+    fn i32(&mut self, name: &'static str, value: i32);
+    fn f32(&mut self, name: &'static str, value: f32);
+    fn bool(&mut self, name: &'static str, value: Option<bool>);
+    fn string(&mut self, name: &'static str, value: &str);
+    fn bytes(&mut self, name: &'static str, value: &[u8]);
+    fn ids(&mut self, name: &'static str, value: &[u32]);
+    fn position(&mut self, name: &'static str, value: &Position);
+    fn target(&mut self, name: &'static str, value: &Target);
+    fn formation(&mut self, name: &'static str, value: Option<&Formation>);
+    fn lua(&mut self, name: &'static str, value: &LuaObject);
+
+    /// A value of `UnitCommandType`.
+    fn unit_command_type(&mut self, name: &'static str, value: u8) {
+        self.u8(name, value);
+    }
+}
+
+/// Pass every field of `command` to `visitor`. The command name is not included.
+pub fn visit_command(command: &ReplayCommand, visitor: &mut impl FieldVisitor) {
+    use ReplayCommand::*;
+
+    match command {
+        Advance { ticks } => visitor.u32("ticks", *ticks),
+        SetCommandSource { id } => visitor.u8("id", *id),
+        CommandSourceTerminated | RequestPause | Resume | SingleStep | EndGame => {}
+        VerifyChecksum { digest, tick } => {
+            visitor.bytes("digest", digest);
+            visitor.u32("tick", *tick);
+        }
+        CreateUnit {
+            army,
+            blueprint,
+            x,
+            z,
+            heading,
+        } => {
+            visitor.u8("army", *army);
+            visitor.string("blueprint", blueprint);
+            visitor.f32("x", *x);
+            visitor.f32("z", *z);
+            visitor.f32("heading", *heading);
+        }
+        CreateProp {
+            blueprint,
+            position,
+        } => {
+            visitor.string("blueprint", blueprint);
+            visitor.position("position", position);
+        }
+        DestroyEntity { unit } => visitor.u32("unit", *unit),
+        WarpEntity { unit, x, y, z } => {
+            visitor.u32("unit", *unit);
+            visitor.f32("x", *x);
+            visitor.f32("y", *y);
+            visitor.f32("z", *z);
+        }
+        ProcessInfoPair { unit, arg1, arg2 } => {
+            visitor.u32("unit", *unit);
+            visitor.string("arg1", arg1);
+            visitor.string("arg2", arg2);
+        }
+        IssueCommand(game_command) | IssueFactoryCommand(game_command) => {
+            visit_game_command(game_command, visitor)
+        }
+        IncreaseCommandCount { id, delta } | DecreaseCommandCount { id, delta } => {
+            visitor.u32("id", *id);
+            visitor.i32("delta", *delta);
+        }
+        SetCommandTarget { id, target } => {
+            visitor.u32("id", *id);
+            visitor.target("target", target);
+        }
+        SetCommandType { id, type_ } => {
+            visitor.u32("id", *id);
+            visitor.unit_command_type("type_", *type_);
+        }
+        SetCommandCells {
+            id,
+            cells,
+            position,
+        } => {
+            visitor.u32("id", *id);
+            visitor.lua("cells", cells);
+            visitor.position("position", position);
+        }
+        RemoveCommandFromQueue { id, unit } => {
+            visitor.u32("id", *id);
+            visitor.u32("unit", *unit);
+        }
+        DebugCommand {
+            command,
+            position,
+            focus_army,
+            selection,
+        } => {
+            visitor.string("command", command);
+            visitor.position("position", position);
+            visitor.u8("focus_army", *focus_army);
+            visitor.ids("selection", selection);
+        }
+        ExecuteLuaInSim { code } => visitor.string("code", code),
+        LuaSimCallback {
+            func,
+            args,
+            selection,
+        } => {
+            visitor.string("func", func);
+            visitor.lua("args", args);
+            visitor.ids("selection", selection);
+        }
+    }
+}
+
+fn visit_game_command(game_command: &GameCommand, visitor: &mut impl FieldVisitor) {
+    visitor.ids("entity_ids", &game_command.entity_ids);
+    visitor.u32("id", game_command.id);
+    visitor.u32(
+        "coordinated_attack_cmd_id",
+        game_command.coordinated_attack_cmd_id,
+    );
+    visitor.unit_command_type("type", game_command.type_);
+    visitor.i32("arg2", game_command.arg2);
+    visitor.target("target", &game_command.target);
+    visitor.u8("arg3", game_command.arg3);
+    visitor.formation("formation", game_command.formation.as_ref());
+    visitor.string("blueprint", &game_command.blueprint);
+    visitor.u32("arg4", game_command.arg4);
+    visitor.u32("arg5", game_command.arg5);
+    visitor.u32("arg6", game_command.arg6);
+    visitor.lua("upgrades", &game_command.upgrades);
+    visitor.bool("clear_queue", game_command.clear_queue);
+}
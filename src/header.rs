@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+use faf_replay_parser::{ReplayReadError, ReplayResult};
+
+/// The parts of the replay header that can be read without decoding any Lua data
+pub struct HeaderInfo {
+    pub scfa_version: String,
+    pub replay_version: String,
+    pub map_file: String,
+    pub players: HashMap<String, u32>,
+    pub army_count: usize,
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> ReplayResult<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(ReplayReadError::IO(Error::new(
+                ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            )));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> ReplayResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> ReplayResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_string(&mut self) -> ReplayResult<String> {
+        let len = match self.data.iter().position(|b| *b == 0) {
+            Some(len) => len,
+            None => self.data.len() + 1,
+        };
+        let bytes = self.take(len)?;
+        self.take(1)?;
+        String::from_utf8(bytes.to_vec()).map_err(ReplayReadError::MalformedUtf8)
+    }
+
+    fn skip_string(&mut self) -> ReplayResult<()> {
+        match self.data.iter().position(|b| *b == 0) {
+            Some(len) => self.take(len + 1).map(|_| ()),
+            None => self.take(self.data.len() + 1).map(|_| ()),
+        }
+    }
+
+    /// Skip a Lua object prefixed by its size
+    fn skip_lua(&mut self) -> ReplayResult<()> {
+        let size = self.read_u32()? as usize;
+        self.take(size).map(|_| ())
+    }
+}
+
+/// Read the header fields that come before the army tables, skipping the `mods` and `scenario`
+/// Lua objects entirely.
+pub fn scan_header(data: &[u8]) -> ReplayResult<HeaderInfo> {
+    let mut cursor = Cursor { data };
+
+    let scfa_version = cursor.read_string()?;
+    // Skip the string "\r\n\x00"
+    cursor.skip_string()?;
+    let version_and_mapname = cursor.read_string()?;
+    let mut version_and_mapname = version_and_mapname.splitn(2, "\r\n");
+    let replay_version = match version_and_mapname.next() {
+        Some(s) => s.to_string(),
+        None => return Err(ReplayReadError::Malformed("missing replay version")),
+    };
+    let map_file = match version_and_mapname.next() {
+        Some(s) => s.to_string(),
+        None => return Err(ReplayReadError::Malformed("missing map name")),
+    };
+    // Skip the string "\r\n\x1a\x00"
+    cursor.skip_string()?;
+
+    // Skip mods and scenario
+    cursor.skip_lua()?;
+    cursor.skip_lua()?;
+
+    let num_sources = cursor.read_u8()? as usize;
+    let mut players = HashMap::new();
+    for _ in 0..num_sources {
+        let name = cursor.read_string()?;
+        let player_id = cursor.read_u32()?;
+        players.insert(name, player_id);
+    }
+
+    // Skip cheats_enabled
+    cursor.read_u8()?;
+    let army_count = cursor.read_u8()? as usize;
+
+    Ok(HeaderInfo {
+        scfa_version,
+        replay_version,
+        map_file,
+        players,
+        army_count,
+    })
+}
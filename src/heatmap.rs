@@ -0,0 +1,155 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::cell::RefCell;
+
+use crate::convert_result;
+use crate::map::MapInfo;
+use crate::sim::process_command;
+
+use faf_replay_parser::scfa::replay::{replay_command, Position, ReplayCommand, Target};
+use faf_replay_parser::scfa::{ParserBuilder, ReplayHeader};
+
+static HEATMAP_COMMANDS: [u8; 5] = [
+    replay_command::ADVANCE,
+    replay_command::SET_COMMAND_SOURCE,
+    replay_command::ISSUE_COMMAND,
+    replay_command::SET_COMMAND_TARGET,
+    replay_command::SET_COMMAND_CELLS,
+];
+
+/// Command positions binned into a `sources x rows x cols` grid
+struct Heatmap {
+    width: f32,
+    height: f32,
+    rows: usize,
+    cols: usize,
+    sources: usize,
+    counts: Vec<u32>,
+}
+
+impl Heatmap {
+    fn new(size: (u32, u32), grid: (usize, usize), sources: usize) -> Heatmap {
+        Heatmap {
+            width: size.0 as f32,
+            height: size.1 as f32,
+            rows: grid.0,
+            cols: grid.1,
+            sources,
+            counts: vec![0; sources * grid.0 * grid.1],
+        }
+    }
+
+    fn process(&mut self, source: u8, command: &ReplayCommand) {
+        use ReplayCommand::*;
+
+        match command {
+            IssueCommand(cmd) => self.add_target(source, &cmd.target),
+            SetCommandTarget { target, .. } => self.add_target(source, target),
+            SetCommandCells { position, .. } => self.add(source, position),
+            _ => {}
+        }
+    }
+
+    fn add_target(&mut self, source: u8, target: &Target) {
+        if let Target::Position(position) = target {
+            self.add(source, position);
+        }
+    }
+
+    /// Positions outside of the map are counted in the nearest cell on the edge
+    fn add(&mut self, source: u8, position: &Position) {
+        let source = source as usize;
+        if source >= self.sources {
+            self.counts.resize((source + 1) * self.rows * self.cols, 0);
+            self.sources = source + 1;
+        }
+
+        let col = bin(position.x, self.width, self.cols);
+        let row = bin(position.z, self.height, self.rows);
+        self.counts[(source * self.rows + row) * self.cols + col] += 1;
+    }
+
+    fn to_numpy(&self, py: Python, source: Option<u8>) -> PyResult<PyObject> {
+        let numpy = py.import("numpy")?;
+        let grid = self.rows * self.cols;
+        let (counts, shape) = match source {
+            Some(source) => {
+                let start = source as usize * grid;
+                let counts = self.counts.get(start..start + grid).unwrap_or(&[]);
+                (counts, vec![self.rows, self.cols])
+            }
+            None => (&self.counts[..], vec![self.sources, self.rows, self.cols]),
+        };
+
+        if counts.is_empty() {
+            return Ok(numpy.call_method1("zeros", (shape, "<u4"))?.into_py(py));
+        }
+        let bytes: Vec<u8> = counts.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let array = numpy
+            .call_method1("frombuffer", (PyBytes::new(py, &bytes), "<u4"))?
+            .call_method1("reshape", (shape,))?
+            .call_method0("copy")?;
+
+        Ok(array.into_py(py))
+    }
+}
+
+fn bin(value: f32, size: f32, bins: usize) -> usize {
+    let index = (value / size * bins as f32).floor();
+    if index < 0.0 {
+        0
+    } else {
+        (index as usize).min(bins - 1)
+    }
+}
+
+fn map_size(header: &ReplayHeader) -> PyResult<(u32, u32)> {
+    MapInfo::new(&header.map_file, &header.scenario)
+        .size
+        .filter(|(w, h)| *w > 0 && *h > 0)
+        .ok_or_else(|| PyValueError::new_err("map size is missing from the replay header"))
+}
+
+/// command_heatmap(data: bytes, grid: Tuple[int, int] = (64, 64), source: Optional[int] = None) -> numpy.ndarray
+///
+/// Parse a replay and count the `Position` targets of `IssueCommand`, `SetCommandTarget` and
+/// `SetCommandCells` commands in a `grid` of `(rows, cols)` cells spanning the map. Rows run
+/// along the map's `z` axis and columns along its `x` axis.
+///
+/// Returns a `uint32` array of shape `(sources, rows, cols)` indexed by command source, or
+/// `(rows, cols)` for the given `source`. Requires numpy.
+#[pyfunction(grid = "(64, 64)", source = "None")]
+#[pyo3(text_signature = "(data, grid=(64, 64), source=None)")]
+pub fn command_heatmap(
+    py: Python,
+    data: &PyBytes,
+    grid: (usize, usize),
+    source: Option<u8>,
+) -> PyResult<PyObject> {
+    if grid.0 == 0 || grid.1 == 0 {
+        return Err(PyValueError::new_err("grid dimensions must be positive"));
+    }
+
+    let mut bytes = data.as_bytes();
+    let parser = ParserBuilder::new()
+        .commands(&HEATMAP_COMMANDS)
+        .save_commands(false)
+        .stop_on_desync(false)
+        .build();
+
+    let header = convert_result(parser.parse_header(&mut bytes))?;
+    let heatmap = Heatmap::new(map_size(&header)?, grid, header.players.len());
+
+    let (body, heatmap) = py.allow_threads(|| {
+        let heatmap = RefCell::new(heatmap);
+        let body = parser.parse_body_with_callback(&mut bytes, |sim, command| {
+            heatmap.borrow_mut().process(sim.command_source, command);
+            process_command(sim, command)
+        });
+        (body, heatmap.into_inner())
+    });
+    convert_result(body)?;
+
+    heatmap.to_numpy(py, source)
+}
@@ -0,0 +1,205 @@
+use serde_json::{json, Map, Value};
+
+use crate::columns::command_id;
+use crate::fields::{visit_command, FieldVisitor};
+use faf_replay_parser::lua::LuaObject;
+use faf_replay_parser::scfa::replay::replay_command::NAMES;
+use faf_replay_parser::scfa::replay::{
+    Formation, Position, Replay, ReplayBody, ReplayCommand, ReplayHeader, SimData, Target,
+};
+
+/// Like `Replay::into_py` but producing a JSON value. Unlike the python dictionaries, everything
+/// here can be serialized:
+///
+/// - Lua strings are decoded as utf8, replacing invalid sequences.
+/// - Lua tables with the keys `1..n` become arrays and all other keys are converted to strings.
+/// - Checksums are hex encoded.
+pub fn replay_to_json(replay: &Replay) -> Value {
+    json!({
+        "header": header_to_json(&replay.header),
+        "body": body_to_json(&replay.body),
+    })
+}
+
+pub fn header_to_json(header: &ReplayHeader) -> Value {
+    json!({
+        "scfa_version": header.scfa_version,
+        "replay_version": header.replay_version,
+        "map_file": header.map_file,
+        "mods": lua_to_json(&header.mods),
+        "scenario": lua_to_json(&header.scenario),
+        "players": header.players,
+        "cheats_enabled": header.cheats_enabled,
+        "army_count": header.army_count,
+        "armies": armies_to_json(header),
+        "seed": header.seed,
+    })
+}
+
+/// The armies of the header keyed by their command source
+pub fn armies_to_json(header: &ReplayHeader) -> Value {
+    let armies: Map<String, Value> = header
+        .armies
+        .iter()
+        .map(|(k, v)| (k.to_string(), lua_to_json(v)))
+        .collect();
+
+    Value::Object(armies)
+}
+
+pub fn body_to_json(body: &ReplayBody) -> Value {
+    json!({
+        "sim": sim_to_json(&body.sim),
+        "commands": body.commands.iter().map(command_to_json).collect::<Vec<Value>>(),
+    })
+}
+
+pub fn sim_to_json(sim: &SimData) -> Value {
+    let players_last_tick: Map<String, Value> = sim
+        .players_last_tick
+        .iter()
+        .map(|(k, v)| (k.to_string(), json!(v)))
+        .collect();
+
+    json!({
+        "tick": sim.tick,
+        "command_source": sim.command_source,
+        "players_last_tick": players_last_tick,
+        "checksum": hex(&sim.checksum),
+        "checksum_tick": sim.checksum_tick,
+        "desync_tick": sim.desync_tick,
+        "desync_ticks": sim.desync_ticks,
+    })
+}
+
+pub fn command_to_json(command: &ReplayCommand) -> Value {
+    let mut fields = JsonFields(Map::new());
+    fields.set("name", json!(NAMES[command_id(command) as usize]));
+
+    visit_command(command, &mut fields);
+
+    Value::Object(fields.0)
+}
+
+/// Inserts each field of a command into a JSON object. Unit command types are left as integers.
+struct JsonFields(Map<String, Value>);
+
+impl JsonFields {
+    fn set(&mut self, name: &str, value: Value) {
+        self.0.insert(name.to_string(), value);
+    }
+}
+
+impl FieldVisitor for JsonFields {
+    fn u8(&mut self, name: &'static str, value: u8) {
+        self.set(name, json!(value));
+    }
+
+    fn u32(&mut self, name: &'static str, value: u32) {
+        self.set(name, json!(value));
+    }
+
+    fn i32(&mut self, name: &'static str, value: i32) {
+        self.set(name, json!(value));
+    }
+
+    fn f32(&mut self, name: &'static str, value: f32) {
+        self.set(name, json!(value));
+    }
+
+    fn bool(&mut self, name: &'static str, value: Option<bool>) {
+        self.set(name, json!(value));
+    }
+
+    fn string(&mut self, name: &'static str, value: &str) {
+        self.set(name, json!(value));
+    }
+
+    fn bytes(&mut self, name: &'static str, value: &[u8]) {
+        self.set(name, Value::String(hex(value)));
+    }
+
+    fn ids(&mut self, name: &'static str, value: &[u32]) {
+        self.set(name, json!(value));
+    }
+
+    fn position(&mut self, name: &'static str, value: &Position) {
+        self.set(name, position_to_json(value));
+    }
+
+    fn target(&mut self, name: &'static str, value: &Target) {
+        self.set(name, target_to_json(value));
+    }
+
+    fn formation(&mut self, name: &'static str, value: Option<&Formation>) {
+        self.set(name, json!(value.map(formation_to_json)));
+    }
+
+    fn lua(&mut self, name: &'static str, value: &LuaObject) {
+        self.set(name, lua_to_json(value));
+    }
+}
+
+fn position_to_json(position: &Position) -> Value {
+    json!({ "x": position.x, "y": position.y, "z": position.z })
+}
+
+fn target_to_json(target: &Target) -> Value {
+    match target {
+        Target::None => Value::Null,
+        Target::Entity { id } => json!({ "id": id }),
+        Target::Position(p) => position_to_json(p),
+    }
+}
+
+fn formation_to_json(formation: &Formation) -> Value {
+    json!({
+        "a": formation.a,
+        "b": formation.b,
+        "c": formation.c,
+        "d": formation.d,
+        "scale": formation.scale,
+    })
+}
+
+pub fn lua_to_json(obj: &LuaObject) -> Value {
+    match obj {
+        LuaObject::Float(f) => json!(f),
+        LuaObject::String(s) => Value::String(String::from_utf8_lossy(s.as_bytes()).into_owned()),
+        LuaObject::Unicode(s) => Value::String(s.clone()),
+        LuaObject::Nil => Value::Null,
+        LuaObject::Bool(b) => Value::Bool(*b),
+        LuaObject::Table(t) => {
+            // Lua arrays are tables with the keys 1 to n
+            let mut items = Vec::with_capacity(t.len());
+            for i in 1..=t.len() {
+                match t.get(&LuaObject::Float(i as f32)) {
+                    Some(v) => items.push(lua_to_json(v)),
+                    None => break,
+                }
+            }
+            if !t.is_empty() && items.len() == t.len() {
+                return Value::Array(items);
+            }
+
+            let map: Map<String, Value> = t
+                .iter()
+                .map(|(k, v)| (lua_key_to_string(k), lua_to_json(v)))
+                .collect();
+            Value::Object(map)
+        }
+    }
+}
+
+fn lua_key_to_string(key: &LuaObject) -> String {
+    match key {
+        LuaObject::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        LuaObject::String(s) => String::from_utf8_lossy(s.as_bytes()).into_owned(),
+        LuaObject::Unicode(s) => s.clone(),
+        other => format!("{}", other),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
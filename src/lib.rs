@@ -4,16 +4,54 @@ extern crate pyo3;
 use pyo3::create_exception;
 use pyo3::exceptions;
 use pyo3::prelude::*;
-use pyo3::types::{PyByteArray, PyBytes};
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyTuple};
 use pyo3::wrap_pyfunction;
 use std::io::ErrorKind;
 
 use faf_replay_parser::scfa;
 
+/// Implement `PickleState` for a struct from a single list of its fields. Each field is stored in
+/// the state dictionary under its own name.
+macro_rules! pickle_state {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl crate::PickleState for $ty {
+            fn state(&self, py: Python) -> PyResult<PyObject> {
+                let state = pyo3::types::PyDict::new(py);
+                $(state.set_item(stringify!($field), &self.$field)?;)*
+                Ok(state.into_py(py))
+            }
+
+            fn from_state(state: &pyo3::types::PyDict) -> PyResult<Self> {
+                Ok($ty {
+                    $($field: crate::state_item(state, stringify!($field))?,)*
+                })
+            }
+        }
+    };
+}
+
+mod arrow;
+mod audit;
+mod blueprints;
+mod cdata;
+mod columns;
 mod constants;
+mod fields;
+mod header;
+mod heatmap;
+mod json;
 mod lua;
+mod map;
+mod mods;
+mod options;
 mod parser;
+mod pauses;
+mod player;
+mod queues;
 mod replay;
+mod sim;
+mod summary;
+mod teams;
 
 struct ReplayReadError(faf_replay_parser::ReplayReadError);
 
@@ -48,6 +86,29 @@ fn convert_result<T>(res: Result<T, faf_replay_parser::ReplayReadError>) -> PyRe
     }
 }
 
+/// Conversion between a class and the dictionary of its fields that is pickled by `reduce`.
+/// Implemented with `pickle_state!`.
+trait PickleState: Sized {
+    fn state(&self, py: Python) -> PyResult<PyObject>;
+    fn from_state(state: &PyDict) -> PyResult<Self>;
+}
+
+fn state_item<'p, T: FromPyObject<'p>>(state: &'p PyDict, name: &str) -> PyResult<T> {
+    state
+        .get_item(name)
+        .ok_or_else(|| exceptions::PyKeyError::new_err(name.to_string()))?
+        .extract()
+}
+
+/// Implementation of `__reduce__` for classes that are only created by the parser. They are
+/// rebuilt from `state` by their `_unpickle` static method instead of a `#[new]` constructor.
+fn reduce(obj: &PyAny, state: Vec<PyObject>) -> PyResult<(PyObject, Py<PyTuple>)> {
+    let py = obj.py();
+    let unpickle = obj.get_type().getattr("_unpickle")?;
+
+    Ok((unpickle.into_py(py), PyTuple::new(py, state).into_py(py)))
+}
+
 impl From<faf_replay_parser::ReplayReadError> for ReplayReadError {
     fn from(obj: faf_replay_parser::ReplayReadError) -> ReplayReadError {
         ReplayReadError(obj)
@@ -96,10 +157,87 @@ fn body_ticks(any: &PyAny) -> PyResult<u32> {
     ))
 }
 
+/// body_info(body: Union[bytes, bytearray]) -> dict
+///
+/// Like `body_ticks` but also decodes the commands that update the sim state. Returns a dict
+/// with the same keys as `Parser.parse(...)["body"]["sim"]` and an additional `command_counts`
+/// key mapping each `CommandType` found in the body to the number of times it appeared.
+/// Desyncs are recorded in `desync_ticks` without raising an error.
+/// Raises `ReplayReadError` if the body data is malformed.
+#[pyfunction]
+#[pyo3(text_signature = "(body)")]
+fn body_info(py: Python, any: &PyAny) -> PyResult<PyObject> {
+    let (sim, counts) = if any.is_instance_of::<PyBytes>()? {
+        let bytes = any.downcast::<PyBytes>().unwrap();
+        convert_result(sim::scan_body(bytes.as_bytes()))?
+    } else if any.is_instance_of::<PyByteArray>()? {
+        let bytearray = any.downcast::<PyByteArray>().unwrap();
+        unsafe { convert_result(sim::scan_body(bytearray.as_bytes()))? }
+    } else {
+        return Err(PyErr::new::<exceptions::PyTypeError, _>(
+            "'body' must be bytes or bytearray",
+        ));
+    };
+
+    let res = replay::SimData(sim).into_py(py);
+    let command_counts = PyDict::new(py);
+    for (id, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+        command_counts.set_item(constants::command_type(py).member(id as u8, py), count)?;
+    }
+    res.cast_as::<PyDict>(py)?
+        .set_item("command_counts", command_counts)?;
+
+    Ok(res)
+}
+
+/// header_info(replay: Union[bytes, bytearray]) -> dict
+///
+/// Read `scfa_version`, `replay_version`, `map_file`, `players` and `army_count` from the
+/// replay header without decoding any of the Lua tables. The values are the same as the ones
+/// returned by `Parser.parse_header`.
+/// Raises `ReplayReadError` if the header data is malformed.
+#[pyfunction]
+#[pyo3(text_signature = "(replay)")]
+fn header_info(py: Python, any: &PyAny) -> PyResult<PyObject> {
+    let info = if any.is_instance_of::<PyBytes>()? {
+        let bytes = any.downcast::<PyBytes>().unwrap();
+        convert_result(header::scan_header(bytes.as_bytes()))?
+    } else if any.is_instance_of::<PyByteArray>()? {
+        let bytearray = any.downcast::<PyByteArray>().unwrap();
+        unsafe { convert_result(header::scan_header(bytearray.as_bytes()))? }
+    } else {
+        return Err(PyErr::new::<exceptions::PyTypeError, _>(
+            "'replay' must be bytes or bytearray",
+        ));
+    };
+
+    let res = PyDict::new(py);
+    res.set_item("scfa_version", info.scfa_version)?;
+    res.set_item("replay_version", info.replay_version)?;
+    res.set_item("map_file", info.map_file)?;
+    res.set_item("players", info.players)?;
+    res.set_item("army_count", info.army_count)?;
+
+    Ok(res.into_py(py))
+}
+
 /// Supreme Commander Forged Alliance replay parser
 #[pymodule]
 fn _fafreplay(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<parser::ParserWrap>()?;
+    m.add_class::<player::PlayerInfo>()?;
+    m.add_class::<options::GameOptions>()?;
+    m.add_class::<map::MapInfo>()?;
+    m.add_class::<mods::ModInfo>()?;
+    m.add_class::<teams::Teams>()?;
+    m.add_class::<queues::CommandQueueTracker>()?;
+    m.add_class::<queues::QueuedCommand>()?;
+    m.add_class::<queues::ProductionEvent>()?;
+    m.add_class::<pauses::Pause>()?;
+    m.add_class::<audit::AuditReport>()?;
+    m.add_class::<summary::Summary>()?;
+    m.add_class::<lua::LuaTableProxy>()?;
+    m.add_class::<arrow::ArrowBatch>()?;
     m.add("ReplayReadError", py.get_type::<PyReplayReadError>())?;
     m.add(
         "ReplayDesyncedError",
@@ -107,8 +245,15 @@ fn _fafreplay(py: Python, m: &PyModule) -> PyResult<()> {
     )?;
     m.add_wrapped(wrap_pyfunction!(body_offset))?;
     m.add_wrapped(wrap_pyfunction!(body_ticks))?;
+    m.add_wrapped(wrap_pyfunction!(body_info))?;
+    m.add_wrapped(wrap_pyfunction!(header_info))?;
+    m.add_wrapped(wrap_pyfunction!(pauses::pause_timeline))?;
+    m.add_wrapped(wrap_pyfunction!(audit::audit_report))?;
+    m.add_wrapped(wrap_pyfunction!(heatmap::command_heatmap))?;
+    m.add_wrapped(wrap_pyfunction!(summary::summarize))?;
 
     constants::add_constants(m)?;
+    blueprints::add_blueprints(m)?;
 
     Ok(())
 }
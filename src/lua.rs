@@ -1,13 +1,21 @@
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::PyKeyError;
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyDict};
-use std::collections::HashMap;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString};
+use std::sync::Arc;
 
 pub struct LuaObject(pub faf_replay_parser::lua::LuaObject);
-pub struct LuaTable(
-    pub HashMap<faf_replay_parser::lua::LuaObject, faf_replay_parser::lua::LuaObject>,
-);
+/// Like `LuaObject` but converts a borrowed object by copying it. `LuaObject` converts through
+/// this as well since the values are copied into python objects either way.
+pub struct LuaObjectRef<'a>(pub &'a faf_replay_parser::lua::LuaObject);
 
 impl IntoPy<PyObject> for LuaObject {
+    fn into_py(self, py: Python) -> PyObject {
+        LuaObjectRef(&self.0).into_py(py)
+    }
+}
+
+impl IntoPy<PyObject> for LuaObjectRef<'_> {
     fn into_py(self, py: Python) -> PyObject {
         use faf_replay_parser::lua::LuaObject::*;
 
@@ -17,34 +25,264 @@ impl IntoPy<PyObject> for LuaObject {
             Unicode(s) => s.into_py(py),
             Nil => ().into_py(py),
             Bool(b) => b.into_py(py),
-            Table(t) => LuaTable(t).into_py(py),
+            Table(t) => {
+                let res = PyDict::new(py);
+
+                for (k, v) in t {
+                    res.set_item(LuaObjectRef(k).into_py(py), LuaObjectRef(v).into_py(py))
+                        .unwrap();
+                }
+
+                res.into_py(py)
+            }
         }
     }
 }
 
-impl IntoPy<PyObject> for LuaTable {
-    fn into_py(self, py: Python) -> PyObject {
-        let res = PyDict::new(py);
+/// A read only mapping over a Lua table from the replay header, returned by parsers created with
+/// `lazy=True`. Sub-tables are only converted when they are accessed, `to_python` converts the
+/// whole table to a `dict`. Pickling or copying a `LuaTable` produces a `dict`.
+///
+/// Keys are looked up the same way they appear in the converted `dict`, so numeric keys can be
+/// given as `int` or `float`.
+#[pyclass(name = "LuaTable", module = "fafreplay")]
+pub struct LuaTableProxy {
+    root: Arc<faf_replay_parser::lua::LuaObject>,
+    path: Option<Arc<LuaPath>>,
+}
+
+/// Keys leading from the root table to a sub-table. The path of a sub-table links to the path of
+/// its parent, so accessing a nested table only copies its own key.
+struct LuaPath {
+    parent: Option<Arc<LuaPath>>,
+    key: faf_replay_parser::lua::LuaObject,
+}
+
+#[pymethods]
+impl LuaTableProxy {
+    /// Convert the whole table to a `dict`, the same way `Parser.parse` does without `lazy`.
+    fn to_python(&self, py: Python) -> PyObject {
+        LuaObjectRef(self.object()).into_py(py)
+    }
+
+    fn get(&self, py: Python, key: &PyAny, default: Option<PyObject>) -> PyResult<PyObject> {
+        Ok(match self.lookup(key)? {
+            Some((k, v)) => self.value_into_py(k, v, py)?,
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    fn keys(&self, py: Python) -> Vec<PyObject> {
+        self.table()
+            .keys()
+            .map(|k| LuaObjectRef(k).into_py(py))
+            .collect()
+    }
+
+    fn values(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        self.table()
+            .iter()
+            .map(|(k, v)| self.value_into_py(k, v, py))
+            .collect()
+    }
+
+    fn items(&self, py: Python) -> PyResult<Vec<(PyObject, PyObject)>> {
+        self.table()
+            .iter()
+            .map(|(k, v)| Ok((LuaObjectRef(k).into_py(py), self.value_into_py(k, v, py)?)))
+            .collect()
+    }
 
-        for (k, v) in self.0 {
-            res.set_item::<PyObject, PyObject>(LuaObject(k).into_py(py), LuaObject(v).into_py(py))
-                .unwrap();
+    fn __getitem__(&self, py: Python, key: &PyAny) -> PyResult<PyObject> {
+        match self.lookup(key)? {
+            Some((k, v)) => self.value_into_py(k, v, py),
+            None => Err(PyKeyError::new_err(key.into_py(py))),
         }
+    }
+
+    fn __contains__(&self, key: &PyAny) -> PyResult<bool> {
+        Ok(self.lookup(key)?.is_some())
+    }
+
+    fn __len__(&self) -> usize {
+        self.table().len()
+    }
+
+    fn __iter__(&self, py: Python) -> PyResult<PyObject> {
+        let keys = PyList::new(py, self.keys(py));
+        Ok(keys.call_method0("__iter__")?.into_py(py))
+    }
+
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp, py: Python) -> PyResult<PyObject> {
+        let other = match other.extract::<PyRef<LuaTableProxy>>() {
+            Ok(proxy) => proxy.to_python(py),
+            Err(_) => other.into_py(py),
+        };
+        let this = self.to_python(py);
+        match op {
+            CompareOp::Eq => Ok(this.as_ref(py).eq(other)?.into_py(py)),
+            CompareOp::Ne => Ok(this.as_ref(py).ne(other)?.into_py(py)),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
 
-        res.into_py(py)
+    fn __reduce__(slf: &PyCell<Self>) -> (PyObject, (PyObject,)) {
+        let py = slf.py();
+        (
+            py.get_type::<PyDict>().into_py(py),
+            (slf.borrow().to_python(py),),
+        )
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "LuaTable({})",
+            self.to_python(py).as_ref(py).repr()?
+        ))
     }
 }
 
-pub fn table_into_py<K: IntoPy<PyObject>>(
-    table: HashMap<K, faf_replay_parser::lua::LuaObject>,
-    py: Python,
-) -> PyObject {
-    let res = PyDict::new(py);
+impl LuaTableProxy {
+    /// Wrap a table in a proxy. Other values are converted right away.
+    pub fn new_py(obj: faf_replay_parser::lua::LuaObject, py: Python) -> PyResult<PyObject> {
+        match obj {
+            faf_replay_parser::lua::LuaObject::Table(_) => Ok(Py::new(
+                py,
+                LuaTableProxy {
+                    root: Arc::new(obj),
+                    path: None,
+                },
+            )?
+            .into_py(py)),
+            obj => Ok(LuaObject(obj).into_py(py)),
+        }
+    }
+
+    fn object(&self) -> &faf_replay_parser::lua::LuaObject {
+        resolve(&self.root, self.path.as_deref())
+    }
+
+    fn table(&self) -> &faf_replay_parser::lua::LuaTable {
+        self.object().as_hashmap().unwrap()
+    }
 
-    for (k, v) in table {
-        res.set_item::<PyObject, PyObject>(k.into_py(py), LuaObject(v).into_py(py))
-            .unwrap();
+    fn lookup(
+        &self,
+        key: &PyAny,
+    ) -> PyResult<
+        Option<(
+            &faf_replay_parser::lua::LuaObject,
+            &faf_replay_parser::lua::LuaObject,
+        )>,
+    > {
+        Ok(match lua_key(key)? {
+            Some(key) => self.table().get_key_value(&key),
+            None => None,
+        })
     }
 
-    res.into_py(py)
+    fn value_into_py(
+        &self,
+        key: &faf_replay_parser::lua::LuaObject,
+        value: &faf_replay_parser::lua::LuaObject,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        use faf_replay_parser::lua::LuaObject::Table;
+
+        match (value, copy_key(key)) {
+            (Table(_), Some(key)) => {
+                let path = LuaPath {
+                    parent: self.path.clone(),
+                    key,
+                };
+                Ok(Py::new(
+                    py,
+                    LuaTableProxy {
+                        root: self.root.clone(),
+                        path: Some(Arc::new(path)),
+                    },
+                )?
+                .into_py(py))
+            }
+            _ => Ok(LuaObjectRef(value).into_py(py)),
+        }
+    }
+}
+
+fn resolve<'a>(
+    root: &'a faf_replay_parser::lua::LuaObject,
+    path: Option<&LuaPath>,
+) -> &'a faf_replay_parser::lua::LuaObject {
+    match path {
+        Some(path) => {
+            let parent = resolve(root, path.parent.as_deref());
+            // Proxies are only created for keys that lead to a table
+            parent
+                .as_hashmap()
+                .ok()
+                .and_then(|t| t.get(&path.key))
+                .unwrap()
+        }
+        None => root,
+    }
+}
+
+/// Convert a python object to the Lua key it would have been converted from. Table keys are
+/// always decoded as unicode strings, so `bytes` keys are normalised to unicode as well and a
+/// hash lookup is enough to find them.
+fn lua_key(key: &PyAny) -> PyResult<Option<faf_replay_parser::lua::LuaObject>> {
+    use faf_replay_parser::lua::LuaObject::*;
+
+    Ok(if key.is_instance_of::<PyBool>()? {
+        Some(Bool(key.extract()?))
+    } else if key.is_instance_of::<PyFloat>()? || key.is_instance_of::<PyLong>()? {
+        Some(Float(key.extract()?))
+    } else if key.is_instance_of::<PyString>()? {
+        Some(Unicode(key.extract()?))
+    } else if key.is_instance_of::<PyBytes>()? {
+        std::str::from_utf8(key.extract()?)
+            .ok()
+            .map(|s| Unicode(s.to_string()))
+    } else if key.is_none() {
+        Some(Nil)
+    } else {
+        None
+    })
+}
+
+/// Copy a Lua object that can be used as a key. Tables can't be copied.
+fn copy_key(key: &faf_replay_parser::lua::LuaObject) -> Option<faf_replay_parser::lua::LuaObject> {
+    use faf_replay_parser::lua::LuaObject::*;
+
+    Some(match key {
+        Float(f) => Float(*f),
+        String(s) => String(s.clone()),
+        Unicode(s) => Unicode(s.clone()),
+        Nil => Nil,
+        Bool(b) => Bool(*b),
+        Table(_) => return None,
+    })
+}
+
+/// Look up a string key in a Lua table.
+pub fn get<'a>(
+    table: &'a faf_replay_parser::lua::LuaTable,
+    key: &str,
+) -> Option<&'a faf_replay_parser::lua::LuaObject> {
+    table.get(&faf_replay_parser::lua::LuaObject::from(key))
+}
+
+/// Look up a string key in a Lua table and convert the value to a `String`.
+pub fn get_string(table: &faf_replay_parser::lua::LuaTable, key: &str) -> Option<String> {
+    get(table, key).and_then(|obj| obj.to_string_lossy().ok())
+}
+
+/// Look up a string key in a Lua table and convert the value to a `f32`.
+pub fn get_float(table: &faf_replay_parser::lua::LuaTable, key: &str) -> Option<f32> {
+    get(table, key).and_then(|obj| obj.as_float().ok())
+}
+
+/// Look up a string key in a Lua table and convert the value to a `bool`.
+pub fn get_bool(table: &faf_replay_parser::lua::LuaTable, key: &str) -> Option<bool> {
+    get(table, key).and_then(|obj| obj.as_bool().ok())
 }
@@ -0,0 +1,181 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::collections::HashMap;
+
+use crate::lua::{get, get_float, get_string};
+use crate::{reduce, PickleState};
+use faf_replay_parser::lua::{LuaObject, LuaTable};
+
+/// Map information from `header["map_file"]` and `header["scenario"]`. Fields that can't be
+/// determined are `None`.
+#[pyclass(module = "fafreplay")]
+pub struct MapInfo {
+    #[pyo3(get)]
+    pub map_file: String,
+    /// Name of the map folder, e.g. `setons clutch.v0003`
+    #[pyo3(get)]
+    pub folder: Option<String>,
+    /// Display name from the scenario, falling back to the folder name without the version
+    #[pyo3(get)]
+    pub name: Option<String>,
+    /// Version from the `.vXXXX` folder suffix or the scenario `map_version`
+    #[pyo3(get)]
+    pub version: Option<u32>,
+    /// Map size as `(width, height)`
+    #[pyo3(get)]
+    pub size: Option<(u32, u32)>,
+    /// Playable area as `(width, height)` if it was recorded in the scenario
+    #[pyo3(get)]
+    pub playable_area: Option<(u32, u32)>,
+    /// Start positions by army name as `(x, y, z)`. Only available if the scenario contains the
+    /// map markers.
+    #[pyo3(get)]
+    pub start_positions: Option<HashMap<String, (f32, f32, f32)>>,
+}
+
+pickle_state!(MapInfo {
+    map_file,
+    folder,
+    name,
+    version,
+    size,
+    playable_area,
+    start_positions,
+});
+
+#[pymethods]
+impl MapInfo {
+    #[staticmethod]
+    fn _unpickle(state: &PyDict) -> PyResult<MapInfo> {
+        MapInfo::from_state(state)
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, Py<PyTuple>)> {
+        reduce(slf, vec![slf.borrow().state(slf.py())?])
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MapInfo(name={:?}, version={}, size={})",
+            self.name.as_deref().unwrap_or(""),
+            self.version.map_or("None".to_string(), |v| v.to_string()),
+            self.size
+                .map_or("None".to_string(), |(w, h)| format!("({}, {})", w, h))
+        )
+    }
+}
+
+impl MapInfo {
+    pub fn new(map_file: &str, scenario: &LuaObject) -> MapInfo {
+        let scenario = scenario.as_hashmap().ok();
+
+        let folder = map_folder(map_file);
+        let (folder_name, folder_version) = match folder {
+            Some(ref folder) => split_version(folder),
+            None => (None, None),
+        };
+        let name = scenario
+            .and_then(|s| get_string(s, "name"))
+            .map(|s| strip_loc(&s).to_string())
+            .filter(|s| !s.is_empty())
+            .or(folder_name);
+        let version = folder_version.or_else(|| {
+            scenario
+                .and_then(|s| get_float(s, "map_version"))
+                .map(|f| f as u32)
+        });
+        let size = scenario.and_then(|s| get(s, "size")).and_then(|obj| {
+            let size = obj.as_hashmap().ok()?;
+            Some((index(size, 1)? as u32, index(size, 2)? as u32))
+        });
+
+        MapInfo {
+            map_file: map_file.to_string(),
+            folder,
+            name,
+            version,
+            size,
+            playable_area: scenario.and_then(playable_area),
+            start_positions: scenario.and_then(start_positions),
+        }
+    }
+}
+
+/// `/maps/setons clutch.v0003/setons clutch.scmap` -> `setons clutch.v0003`
+fn map_folder(map_file: &str) -> Option<String> {
+    let mut parts = map_file.split('/').filter(|s| !s.is_empty());
+    match (parts.next(), parts.next()) {
+        (Some(maps), Some(folder)) if maps.eq_ignore_ascii_case("maps") => {
+            Some(folder.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// `setons clutch.v0003` -> (`setons clutch`, 3)
+fn split_version(folder: &str) -> (Option<String>, Option<u32>) {
+    if let Some((name, version)) = folder.rsplit_once(".v") {
+        if let Ok(version) = version.parse() {
+            return (Some(name.to_string()), Some(version));
+        }
+    }
+    (Some(folder.to_string()), None)
+}
+
+/// Remove the localization tag from strings like `<LOC map_Name>Name`
+fn strip_loc(s: &str) -> &str {
+    if s.starts_with("<LOC") {
+        if let Some(end) = s.find('>') {
+            return &s[end + 1..];
+        }
+    }
+    s
+}
+
+fn index(table: &LuaTable, i: u32) -> Option<f32> {
+    table.get(&LuaObject::Float(i as f32))?.as_float().ok()
+}
+
+fn playable_area(scenario: &LuaTable) -> Option<(u32, u32)> {
+    let options = get(scenario, "Options").and_then(|obj| obj.as_hashmap().ok());
+    let dimension = |key| {
+        get_float(scenario, key)
+            .or_else(|| options.and_then(|o| get_string(o, key)?.parse().ok()))
+            .map(|f| f as u32)
+    };
+
+    Some((dimension("PlayableAreaWidth")?, dimension("PlayableAreaHeight")?))
+}
+
+/// Reads the `ARMY_*` markers from `MasterChain._MASTERCHAIN_.Markers`
+fn start_positions(scenario: &LuaTable) -> Option<HashMap<String, (f32, f32, f32)>> {
+    let markers = get(scenario, "MasterChain")?
+        .as_hashmap()
+        .ok()
+        .and_then(|t| get(t, "_MASTERCHAIN_"))?
+        .as_hashmap()
+        .ok()
+        .and_then(|t| get(t, "Markers"))?
+        .as_hashmap()
+        .ok()?;
+
+    let mut positions = HashMap::new();
+    for (name, marker) in markers {
+        let name = match name.to_string_lossy() {
+            Ok(name) if name.starts_with("ARMY_") => name,
+            _ => continue,
+        };
+        let position = marker
+            .as_hashmap()
+            .ok()
+            .and_then(|m| get(m, "position"))
+            .and_then(|p| p.as_hashmap().ok());
+        if let Some(p) = position {
+            if let (Some(x), Some(y), Some(z)) = (index(p, 1), index(p, 2), index(p, 3)) {
+                positions.insert(name, (x, y, z));
+            }
+        }
+    }
+
+    Some(positions)
+}
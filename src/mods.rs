@@ -0,0 +1,103 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+
+use crate::lua::{get, get_bool, get_string};
+use crate::player::repr;
+use crate::{reduce, PickleState};
+use faf_replay_parser::lua::LuaObject;
+
+/// Mod information decoded from one of the tables in `header["mods"]`
+#[pyclass(module = "fafreplay")]
+pub struct ModInfo {
+    #[pyo3(get)]
+    pub uid: String,
+    #[pyo3(get)]
+    pub name: Option<String>,
+    /// The version from `mod_info.lua`. Usually a whole number but some mods use fractions.
+    #[pyo3(get)]
+    pub version: Option<f32>,
+    #[pyo3(get)]
+    pub author: Option<String>,
+    /// Mod folder, e.g. `/mods/autoget_v3`
+    #[pyo3(get)]
+    pub location: Option<String>,
+    /// Whether the mod only changes the UI. These don't affect the simulation and are not needed
+    /// to play back the replay.
+    #[pyo3(get)]
+    pub ui_only: bool,
+}
+
+pickle_state!(ModInfo {
+    uid,
+    name,
+    version,
+    author,
+    location,
+    ui_only,
+});
+
+#[pymethods]
+impl ModInfo {
+    #[staticmethod]
+    fn _unpickle(state: &PyDict) -> PyResult<ModInfo> {
+        ModInfo::from_state(state)
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, Py<PyTuple>)> {
+        reduce(slf, vec![slf.borrow().state(slf.py())?])
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ModInfo(uid={:?}, name={}, version={}, ui_only={})",
+            self.uid,
+            repr(&self.name.as_ref().map(|s| format!("{:?}", s))),
+            repr(&self.version),
+            if self.ui_only { "True" } else { "False" }
+        )
+    }
+}
+
+impl ModInfo {
+    /// The uid is read from the table itself and falls back to the table key, since some replays
+    /// store the mods keyed by uid.
+    pub fn from_lua(key: &LuaObject, info: &LuaObject) -> Option<ModInfo> {
+        let info = info.as_hashmap().ok()?;
+        let uid = get_string(info, "uid").or_else(|| match key {
+            LuaObject::Float(_) => None,
+            key => key.to_string_lossy().ok(),
+        })?;
+
+        Some(ModInfo {
+            uid,
+            name: get_string(info, "name"),
+            version: get(info, "version").and_then(|obj| match obj {
+                LuaObject::Float(f) => Some(*f),
+                obj => obj.to_string_lossy().ok()?.parse().ok(),
+            }),
+            author: get_string(info, "author"),
+            location: get_string(info, "location"),
+            ui_only: get_bool(info, "ui_only").unwrap_or(false),
+        })
+    }
+}
+
+/// Decode all mods in the order they were listed in the header. Entries that are not tables or
+/// that have no uid are skipped.
+pub fn mod_info(mods: &LuaObject) -> Vec<ModInfo> {
+    let mods = match mods.as_hashmap() {
+        Ok(mods) => mods,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut entries: Vec<_> = mods.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| match (a, b) {
+        (LuaObject::Float(a), LuaObject::Float(b)) => a.total_cmp(b),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    });
+
+    entries
+        .into_iter()
+        .filter_map(|(k, v)| ModInfo::from_lua(k, v))
+        .collect()
+}
@@ -0,0 +1,165 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+
+use crate::lua::{get, LuaObjectRef};
+use crate::{reduce, PickleState};
+use faf_replay_parser::lua::{LuaObject, LuaTable};
+
+/// Option keys that are decoded into fields of `GameOptions`
+static KNOWN_OPTIONS: [&str; 13] = [
+    "Victory",
+    "Share",
+    "UnitCap",
+    "CheatsEnabled",
+    "CheatMult",
+    "BuildMult",
+    "Timeouts",
+    "GameSpeed",
+    "FogOfWar",
+    "PrebuiltUnits",
+    "NoRushOption",
+    "ShareUnitCap",
+    "TeamLock",
+];
+
+/// The lobby options from `header["scenario"]["Options"]`. Missing options are filled in with
+/// the lobby defaults. Options that don't have a field are available through `extra`.
+#[pyclass(module = "fafreplay")]
+pub struct GameOptions {
+    #[pyo3(get)]
+    pub victory: String,
+    #[pyo3(get)]
+    pub share: String,
+    #[pyo3(get)]
+    pub unit_cap: u32,
+    #[pyo3(get)]
+    pub cheats_enabled: bool,
+    #[pyo3(get)]
+    pub cheat_mult: f32,
+    #[pyo3(get)]
+    pub build_mult: f32,
+    /// Number of timeouts per player. `-1` means unlimited.
+    #[pyo3(get)]
+    pub timeouts: i32,
+    #[pyo3(get)]
+    pub game_speed: String,
+    #[pyo3(get)]
+    pub fog_of_war: String,
+    #[pyo3(get)]
+    pub prebuilt_units: bool,
+    /// No rush timer in minutes. `0` means off.
+    #[pyo3(get)]
+    pub no_rush: u32,
+    #[pyo3(get)]
+    pub share_unit_cap: String,
+    #[pyo3(get)]
+    pub team_lock: bool,
+    /// All other options as a dictionary of raw Lua values
+    #[pyo3(get)]
+    pub extra: PyObject,
+}
+
+pickle_state!(GameOptions {
+    victory,
+    share,
+    unit_cap,
+    cheats_enabled,
+    cheat_mult,
+    build_mult,
+    timeouts,
+    game_speed,
+    fog_of_war,
+    prebuilt_units,
+    no_rush,
+    share_unit_cap,
+    team_lock,
+    extra,
+});
+
+#[pymethods]
+impl GameOptions {
+    #[staticmethod]
+    fn _unpickle(state: &PyDict) -> PyResult<GameOptions> {
+        GameOptions::from_state(state)
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, Py<PyTuple>)> {
+        reduce(slf, vec![slf.borrow().state(slf.py())?])
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GameOptions(victory={:?}, share={:?}, unit_cap={}, cheats_enabled={})",
+            self.victory,
+            self.share,
+            self.unit_cap,
+            if self.cheats_enabled { "True" } else { "False" }
+        )
+    }
+}
+
+impl GameOptions {
+    pub fn from_scenario(scenario: &LuaObject, py: Python) -> GameOptions {
+        let empty = LuaTable::new();
+        let options = scenario
+            .as_hashmap()
+            .ok()
+            .and_then(|t| get(t, "Options"))
+            .and_then(|obj| obj.as_hashmap().ok())
+            .unwrap_or(&empty);
+
+        let extra = PyDict::new(py);
+        for (k, v) in options {
+            let known = k
+                .to_string_lossy()
+                .map(|k| KNOWN_OPTIONS.contains(&k.as_str()))
+                .unwrap_or(false);
+            if !known {
+                extra
+                    .set_item(LuaObjectRef(k).into_py(py), LuaObjectRef(v).into_py(py))
+                    .unwrap();
+            }
+        }
+
+        GameOptions {
+            victory: string(options, "Victory").unwrap_or_else(|| "demoralization".to_string()),
+            share: string(options, "Share").unwrap_or_else(|| "ShareUntilDeath".to_string()),
+            unit_cap: number(options, "UnitCap").map_or(1000, |f| f as u32),
+            cheats_enabled: flag(options, "CheatsEnabled").unwrap_or(false),
+            cheat_mult: number(options, "CheatMult").unwrap_or(2.0),
+            build_mult: number(options, "BuildMult").unwrap_or(2.0),
+            timeouts: number(options, "Timeouts").map_or(3, |f| f as i32),
+            game_speed: string(options, "GameSpeed").unwrap_or_else(|| "normal".to_string()),
+            fog_of_war: string(options, "FogOfWar").unwrap_or_else(|| "explored".to_string()),
+            prebuilt_units: flag(options, "PrebuiltUnits").unwrap_or(false),
+            no_rush: number(options, "NoRushOption").map_or(0, |f| f as u32),
+            share_unit_cap: string(options, "ShareUnitCap").unwrap_or_else(|| "none".to_string()),
+            team_lock: string(options, "TeamLock").is_none_or(|s| s == "locked"),
+            extra: extra.into_py(py),
+        }
+    }
+}
+
+fn string(options: &LuaTable, key: &str) -> Option<String> {
+    get(options, key).and_then(|obj| obj.to_string_lossy().ok())
+}
+
+/// Lobby options are usually stored as strings, but some mods use numbers
+fn number(options: &LuaTable, key: &str) -> Option<f32> {
+    match get(options, key)? {
+        LuaObject::Float(f) => Some(*f),
+        obj => obj.to_string_lossy().ok()?.parse().ok(),
+    }
+}
+
+/// Flags can be booleans or any of the strings used by the lobby for on and off
+fn flag(options: &LuaTable, key: &str) -> Option<bool> {
+    match get(options, key)? {
+        LuaObject::Bool(b) => Some(*b),
+        obj => match obj.to_string_lossy().ok()?.to_lowercase().as_str() {
+            "true" | "on" | "yes" => Some(true),
+            "false" | "off" | "no" => Some(false),
+            _ => None,
+        },
+    }
+}
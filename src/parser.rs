@@ -1,15 +1,39 @@
-use pyo3::exceptions::PyTypeError;
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyLong};
+use pyo3::types::{PyBytes, PyDict, PyLong};
+use std::cell::RefCell;
+use std::collections::HashSet;
 
+use crate::arrow::{header_batch, ArrowBatch, CommandBatches};
+use crate::columns::{columns_into_py, command_id, CommandColumns};
 use crate::convert_result;
-use crate::replay::{Replay, ReplayBody, ReplayHeader};
+use crate::json::{command_to_json, replay_to_json};
+use crate::replay::{command_to_py, Replay, ReplayBody, ReplayHeader};
+use crate::sim::process_command;
 
+use faf_replay_parser::scfa::replay::{replay_command, ReplayCommand, SimData};
 use faf_replay_parser::scfa::{Parser, ParserBuilder};
+use faf_replay_parser::ReplayResult;
+
+/// Same commands as `ParserBuilder::commands_default`
+const DEFAULT_COMMANDS: [u8; 5] = [
+    replay_command::ADVANCE,
+    replay_command::SET_COMMAND_SOURCE,
+    replay_command::COMMAND_SOURCE_TERMINATED,
+    replay_command::VERIFY_CHECKSUM,
+    replay_command::END_GAME,
+];
 
 #[pyclass(name = "Parser")]
 pub struct ParserWrap {
     parser: Parser,
+    /// Parses the configured commands together with the ones that update the tick and command
+    /// source, without saving them. Used by the methods that collect rows in a callback.
+    row_parser: Parser,
+    /// The configured commands, only these are turned into rows
+    commands: HashSet<u8>,
+    limit: Option<usize>,
+    lazy: bool,
 }
 
 #[pymethods]
@@ -20,10 +44,11 @@ impl ParserWrap {
         commands: Option<&PyAny>,
         save_commands: Option<bool>,
         stop_on_desync: Option<bool>,
+        lazy: Option<bool>,
     ) -> PyResult<ParserWrap> {
         // Configure ParserBuilder from arguments
         let mut builder = ParserBuilder::new().limit(limit);
-        if let Some(seq) = commands {
+        let commands = if let Some(seq) = commands {
             let mut commands = match seq.len() {
                 Ok(len) => Vec::with_capacity(len),
                 Err(_) => Vec::new(),
@@ -38,19 +63,32 @@ impl ParserWrap {
                         .extract()?,
                 );
             }
-            builder = builder.commands(&commands);
+            commands
         } else {
-            builder = builder.commands_default();
-        }
+            DEFAULT_COMMANDS.to_vec()
+        };
+        builder = builder.commands(&commands);
+        let mut row_commands = commands.clone();
+        row_commands.extend([replay_command::ADVANCE, replay_command::SET_COMMAND_SOURCE]);
 
         // Default `save_commands` to false for the python bindings as converting thousands of
         // commands to python dictionaries can be quite expensive.
+        let stop_on_desync = stop_on_desync.unwrap_or(true);
         builder = builder
             .save_commands(save_commands.unwrap_or(false))
-            .stop_on_desync(stop_on_desync.unwrap_or(true));
+            .stop_on_desync(stop_on_desync);
+        let row_parser = ParserBuilder::new()
+            .commands(&row_commands)
+            .save_commands(false)
+            .stop_on_desync(stop_on_desync)
+            .build();
 
         Ok(ParserWrap {
             parser: builder.build(),
+            row_parser,
+            commands: commands.into_iter().collect(),
+            limit,
+            lazy: lazy.unwrap_or(false),
         })
     }
 
@@ -58,19 +96,34 @@ impl ParserWrap {
     #[pyo3(text_signature = "(data)")]
     fn parse(&self, py: Python, data: &PyBytes) -> PyResult<Replay> {
         let mut bytes = data.as_bytes();
-        Ok(Replay(py.allow_threads(|| {
-            convert_result(self.parser.parse(&mut bytes))
-        })?))
+        Ok(Replay(
+            py.allow_threads(|| convert_result(self.parser.parse(&mut bytes)))?,
+            self.lazy,
+        ))
     }
 
     /// Parse a replay header
     #[pyo3(text_signature = "(data)")]
     fn parse_header(&self, py: Python, data: &PyBytes) -> PyResult<ReplayHeader> {
         let mut bytes = data.as_bytes();
-        Ok(ReplayHeader(py.allow_threads(|| {
-            convert_result(self.parser.parse_header(&mut bytes))
-        })?))
+        Ok(ReplayHeader(
+            py.allow_threads(|| convert_result(self.parser.parse_header(&mut bytes)))?,
+            self.lazy,
+        ))
     }
+    /// Parse a replay and serialize it to a JSON string. Unlike the dictionaries returned by
+    /// `parse`, the result can always be serialized: Lua tables with non string keys are
+    /// converted to arrays or objects with string keys, Lua strings are decoded as utf8 and
+    /// checksums are hex encoded.
+    #[pyo3(text_signature = "(data)")]
+    fn parse_json(&self, py: Python, data: &PyBytes) -> PyResult<String> {
+        let mut bytes = data.as_bytes();
+        let replay = py.allow_threads(|| convert_result(self.parser.parse(&mut bytes)))?;
+
+        py.allow_threads(|| serde_json::to_string(&replay_to_json(&replay)))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
     /// Parse a replay body. This implies that the header has already been parsed in order for
     /// `data` to be at the correct offset.
     #[pyo3(text_signature = "(data)")]
@@ -80,4 +133,115 @@ impl ParserWrap {
             convert_result(self.parser.parse_body(&mut bytes))
         })?))
     }
+    /// Parse a replay into a numpy structured array with one row per command. This is much
+    /// faster than `parse` with `save_commands=True` when working with a large number of
+    /// commands. Only the commands configured for this parser are included, but the `tick` and
+    /// `source` columns are always filled in. Requires numpy.
+    ///
+    /// Returns a dictionary with the keys `sim`, `commands` and `blueprints`. The `blueprint`
+    /// column of `commands` is an index into `blueprints` or -1.
+    #[pyo3(text_signature = "(data)")]
+    fn parse_columns(&self, py: Python, data: &PyBytes) -> PyResult<PyObject> {
+        let bytes = data.as_bytes();
+        let (body, columns) = py.allow_threads(|| {
+            let mut columns = CommandColumns::default();
+            let replay = self.parse_rows(bytes, |sim, command| columns.push(sim, command));
+            (replay, columns)
+        });
+
+        columns_into_py(convert_result(body)?.body.sim, columns, py)
+    }
+    /// Parse a replay into arrow record batches.
+    ///
+    /// Returns a dictionary with the keys `header` and `commands`. The header batch contains a
+    /// single row and `commands` maps each parsed command name to a batch of all commands of
+    /// that type. Command columns are named the same as the keys returned by `parse` plus `tick`
+    /// and `source`. Lua values are stored as JSON strings.
+    ///
+    /// The batches are `ArrowBatch` objects which implement the arrow PyCapsule interface, so
+    /// they can be passed directly to libraries such as polars or DuckDB. `ArrowBatch.to_pyarrow`
+    /// converts them to `pyarrow.RecordBatch`.
+    #[pyo3(text_signature = "(data)")]
+    fn parse_arrow(&self, py: Python, data: &PyBytes) -> PyResult<PyObject> {
+        let bytes = data.as_bytes();
+        let (replay, batches) = py.allow_threads(|| {
+            let mut batches = CommandBatches::default();
+            let replay = self.parse_rows(bytes, |sim, command| batches.push(sim, command));
+            (replay, batches)
+        });
+        let replay = convert_result(replay)?;
+
+        let res = PyDict::new(py);
+        res.set_item(
+            "header",
+            Py::new(py, ArrowBatch::new(header_batch(&replay.header, py)))?,
+        )?;
+        res.set_item("commands", batches.into_py(py)?)?;
+
+        Ok(res.into_py(py))
+    }
+
+    /// Parse a replay into a list of `(tick, source, command)` tuples, one for each of the
+    /// configured commands. The tick and source are always tracked, so unlike `parse` there is
+    /// no need to configure `Advance` and `SetCommandSource` and convert them as well. Commands
+    /// are dictionaries like the ones returned by `parse`, or JSON strings like the ones in
+    /// `parse_json` if `json` is true. `limit` applies to the number of returned commands.
+    #[pyo3(text_signature = "(data, json=False)")]
+    fn parse_commands(
+        &self,
+        py: Python,
+        data: &PyBytes,
+        json: Option<bool>,
+    ) -> PyResult<Vec<(u32, u8, PyObject)>> {
+        let bytes = data.as_bytes();
+        if json.unwrap_or(false) {
+            let (replay, rows) = py.allow_threads(|| {
+                let mut rows = Vec::new();
+                let replay = self.parse_rows(bytes, |sim, command| {
+                    let json = command_to_json(command).to_string();
+                    rows.push((sim.tick, sim.command_source, json));
+                });
+                (replay, rows)
+            });
+            convert_result(replay)?;
+
+            return Ok(rows
+                .into_iter()
+                .map(|(tick, source, json)| (tick, source, json.into_py(py)))
+                .collect());
+        }
+
+        // Converting to python objects needs the GIL, so it is held for the whole parse
+        let mut rows = Vec::new();
+        let replay = self.parse_rows(bytes, |sim, command| {
+            rows.push((sim.tick, sim.command_source, command_to_py(command, py)));
+        });
+        convert_result(replay)?;
+
+        Ok(rows)
+    }
+}
+
+impl ParserWrap {
+    /// Parse a replay and call `push` for each of the configured commands before it is
+    /// processed. `Advance` and `SetCommandSource` are always processed so that the tick and
+    /// source passed to `push` are correct. `limit` applies to the number of rows.
+    fn parse_rows(
+        &self,
+        mut bytes: &[u8],
+        push: impl FnMut(&SimData, &ReplayCommand),
+    ) -> ReplayResult<faf_replay_parser::scfa::Replay> {
+        let rows = RefCell::new((push, 0));
+        self.row_parser
+            .parse_with_callback(&mut bytes, |sim, command| {
+                let (push, count) = &mut *rows.borrow_mut();
+                if self.commands.contains(&command_id(command))
+                    && self.limit.is_none_or(|limit| *count < limit)
+                {
+                    push(sim, command);
+                    *count += 1;
+                }
+                process_command(sim, command)
+            })
+    }
 }
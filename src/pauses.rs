@@ -0,0 +1,134 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::cell::RefCell;
+
+use crate::convert_result;
+use crate::player::repr;
+use crate::sim::process_command;
+
+use faf_replay_parser::scfa::replay::{replay_command, ReplayCommand};
+use faf_replay_parser::scfa::ParserBuilder;
+
+static PAUSE_COMMANDS: [u8; 5] = [
+    replay_command::ADVANCE,
+    replay_command::SET_COMMAND_SOURCE,
+    replay_command::REQUEST_PAUSE,
+    replay_command::RESUME,
+    replay_command::SINGLE_STEP,
+];
+
+/// A period in which the game was paused
+#[pyclass(module = "fafreplay")]
+pub struct Pause {
+    /// Command source of the player that requested the pause
+    #[pyo3(get)]
+    pub source: u8,
+    #[pyo3(get)]
+    pub tick: u32,
+    /// Command source of the player that resumed the game, or `None` if it was never resumed
+    #[pyo3(get)]
+    pub resumed_by: Option<u8>,
+    #[pyo3(get)]
+    pub resume_tick: Option<u32>,
+    /// Number of times the game was advanced by a single step while paused
+    #[pyo3(get)]
+    pub single_steps: u32,
+    /// Additional pause requests while the game was already paused, as `(tick, source)`
+    #[pyo3(get)]
+    pub requests: Vec<(u32, u8)>,
+}
+
+#[pymethods]
+impl Pause {
+    /// Game ticks that passed while paused, which can only happen through single steps. The
+    /// replay doesn't record how long a pause lasted in real time.
+    #[getter]
+    fn ticks(&self) -> Option<u32> {
+        self.resume_tick.map(|t| t - self.tick)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Pause(source={}, tick={}, resumed_by={}, resume_tick={}, single_steps={})",
+            self.source,
+            self.tick,
+            repr(&self.resumed_by),
+            repr(&self.resume_tick),
+            self.single_steps
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct PauseTimeline {
+    pub pauses: Vec<Pause>,
+    paused: bool,
+}
+
+impl PauseTimeline {
+    pub fn process(&mut self, tick: u32, source: u8, command: &ReplayCommand) {
+        use ReplayCommand::*;
+
+        match command {
+            RequestPause if self.paused => {
+                if let Some(pause) = self.pauses.last_mut() {
+                    pause.requests.push((tick, source));
+                }
+            }
+            RequestPause => {
+                self.paused = true;
+                self.pauses.push(Pause {
+                    source,
+                    tick,
+                    resumed_by: None,
+                    resume_tick: None,
+                    single_steps: 0,
+                    requests: Vec::new(),
+                });
+            }
+            SingleStep if self.paused => {
+                if let Some(pause) = self.pauses.last_mut() {
+                    pause.single_steps += 1;
+                }
+            }
+            // Every player sends a `Resume` when the game starts, these are not interesting
+            Resume if self.paused => {
+                self.paused = false;
+                if let Some(pause) = self.pauses.last_mut() {
+                    pause.resumed_by = Some(source);
+                    pause.resume_tick = Some(tick);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// pause_timeline(data: bytes) -> List[Pause]
+///
+/// Parse a replay and list every time the game was paused, who paused and resumed it and how
+/// many single steps were taken in between.
+#[pyfunction]
+#[pyo3(text_signature = "(data)")]
+pub fn pause_timeline(py: Python, data: &PyBytes) -> PyResult<Vec<Pause>> {
+    let mut bytes = data.as_bytes();
+    let parser = ParserBuilder::new()
+        .commands(&PAUSE_COMMANDS)
+        .save_commands(false)
+        .stop_on_desync(false)
+        .build();
+
+    let (replay, timeline) = py.allow_threads(|| {
+        let timeline = RefCell::new(PauseTimeline::default());
+        let replay = parser.parse_with_callback(&mut bytes, |sim, command| {
+            timeline
+                .borrow_mut()
+                .process(sim.tick, sim.command_source, command);
+            process_command(sim, command)
+        });
+        (replay, timeline.into_inner())
+    });
+    convert_result(replay)?;
+
+    Ok(timeline.pauses)
+}
@@ -0,0 +1,140 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::collections::HashMap;
+
+use crate::lua::{get_bool, get_float, get_string};
+use crate::{reduce, PickleState};
+use faf_replay_parser::lua::LuaObject;
+
+pub static FACTIONS: [&str; 5] = ["UEF", "Aeon", "Cybran", "Seraphim", "Nomads"];
+
+/// Player information decoded from one of the `armies` tables in the replay header. Any values
+/// that are missing from the table are `None`.
+#[pyclass(module = "fafreplay")]
+pub struct PlayerInfo {
+    /// The key of the army in `header["armies"]`
+    #[pyo3(get)]
+    pub source: u32,
+    #[pyo3(get)]
+    pub name: Option<String>,
+    #[pyo3(get)]
+    pub army_name: Option<String>,
+    /// Faction index starting at 1. See `faction_name` for the name.
+    #[pyo3(get)]
+    pub faction: Option<u32>,
+    #[pyo3(get)]
+    pub team: Option<u32>,
+    #[pyo3(get)]
+    pub start_spot: Option<u32>,
+    #[pyo3(get)]
+    pub color: Option<u32>,
+    #[pyo3(get)]
+    pub ai: bool,
+    #[pyo3(get)]
+    pub ai_personality: Option<String>,
+    #[pyo3(get)]
+    pub civilian: bool,
+    #[pyo3(get)]
+    pub rating_mean: Option<f32>,
+    #[pyo3(get)]
+    pub rating_deviation: Option<f32>,
+    #[pyo3(get)]
+    pub clan: Option<String>,
+    #[pyo3(get)]
+    pub country: Option<String>,
+    #[pyo3(get)]
+    pub owner_id: Option<String>,
+}
+
+pickle_state!(PlayerInfo {
+    source,
+    name,
+    army_name,
+    faction,
+    team,
+    start_spot,
+    color,
+    ai,
+    ai_personality,
+    civilian,
+    rating_mean,
+    rating_deviation,
+    clan,
+    country,
+    owner_id,
+});
+
+#[pymethods]
+impl PlayerInfo {
+    #[staticmethod]
+    fn _unpickle(state: &PyDict) -> PyResult<PlayerInfo> {
+        PlayerInfo::from_state(state)
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, Py<PyTuple>)> {
+        reduce(slf, vec![slf.borrow().state(slf.py())?])
+    }
+
+    /// Name of the faction or `None` if it is unknown
+    #[getter]
+    fn faction_name(&self) -> Option<&'static str> {
+        self.faction
+            .and_then(|f| FACTIONS.get((f as usize).checked_sub(1)?))
+            .copied()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PlayerInfo(name={}, faction={}, team={}, start_spot={})",
+            repr(&self.name.as_ref().map(|s| format!("{:?}", s))),
+            repr(&self.faction_name().map(|s| format!("{:?}", s))),
+            repr(&self.team),
+            repr(&self.start_spot)
+        )
+    }
+}
+
+impl PlayerInfo {
+    pub fn from_army(source: u32, army: &LuaObject) -> Option<PlayerInfo> {
+        let army = army.as_hashmap().ok()?;
+
+        Some(PlayerInfo {
+            source,
+            name: get_string(army, "PlayerName"),
+            army_name: get_string(army, "ArmyName"),
+            faction: get_float(army, "Faction").map(|f| f as u32),
+            team: get_float(army, "Team").map(|f| f as u32),
+            start_spot: get_float(army, "StartSpot").map(|f| f as u32),
+            color: get_float(army, "PlayerColor").map(|f| f as u32),
+            ai: !get_bool(army, "Human").unwrap_or(true),
+            ai_personality: get_string(army, "AIPersonality").filter(|s| !s.is_empty()),
+            civilian: get_bool(army, "Civilian").unwrap_or(false),
+            rating_mean: get_float(army, "MEAN"),
+            rating_deviation: get_float(army, "DEV"),
+            clan: get_string(army, "PlayerClan").filter(|s| !s.is_empty()),
+            country: get_string(army, "Country").filter(|s| !s.is_empty()),
+            owner_id: get_string(army, "OwnerID"),
+        })
+    }
+}
+
+/// Decode all of the army tables, ordered by start spot.
+pub fn player_info(armies: &HashMap<u32, LuaObject>) -> Vec<PlayerInfo> {
+    let mut players: Vec<PlayerInfo> = armies
+        .iter()
+        .filter_map(|(source, army)| PlayerInfo::from_army(*source, army))
+        .collect();
+    players.sort_by(|a, b| {
+        (a.start_spot, &a.army_name, a.source).cmp(&(b.start_spot, &b.army_name, b.source))
+    });
+
+    players
+}
+
+/// Format an option the way python would
+pub fn repr<T: std::fmt::Display>(value: &Option<T>) -> String {
+    match value {
+        Some(v) => v.to_string(),
+        None => "None".to_string(),
+    }
+}
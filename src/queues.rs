@@ -0,0 +1,550 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::constants::unit_command_type;
+use crate::convert_result;
+use crate::player::repr;
+use crate::sim::process_command;
+
+use faf_replay_parser::scfa::replay::{
+    game_command, replay_command, GameCommand, ReplayCommand, Target,
+};
+use faf_replay_parser::scfa::{Parser, ParserBuilder};
+
+/// Commands that reference the command queue, along with the ones needed to keep track of the
+/// tick and command source.
+static QUEUE_COMMANDS: [u8; 9] = [
+    replay_command::ADVANCE,
+    replay_command::SET_COMMAND_SOURCE,
+    replay_command::ISSUE_COMMAND,
+    replay_command::ISSUE_FACTORY_COMMAND,
+    replay_command::INCREASE_COMMAND_COUNT,
+    replay_command::DECREASE_COMMAND_COUNT,
+    replay_command::SET_COMMAND_TARGET,
+    replay_command::SET_COMMAND_TYPE,
+    replay_command::REMOVE_COMMAND_FROM_QUEUE,
+];
+
+/// `Target` without the borrow checker getting in the way of keeping a history
+#[derive(Clone, Debug, PartialEq)]
+enum CommandTarget {
+    None,
+    Entity(u32),
+    Position(f32, f32, f32),
+}
+
+impl From<&Target> for CommandTarget {
+    fn from(target: &Target) -> CommandTarget {
+        match target {
+            Target::None => CommandTarget::None,
+            Target::Entity { id } => CommandTarget::Entity(*id),
+            Target::Position(p) => CommandTarget::Position(p.x, p.y, p.z),
+        }
+    }
+}
+
+/// Same layout as the `target` of parsed commands
+impl IntoPy<PyObject> for CommandTarget {
+    fn into_py(self, py: Python) -> PyObject {
+        let res = PyDict::new(py);
+        match self {
+            CommandTarget::None => return py.None(),
+            CommandTarget::Entity(id) => res.set_item("id", id).unwrap(),
+            CommandTarget::Position(x, y, z) => {
+                res.set_item("x", x).unwrap();
+                res.set_item("y", y).unwrap();
+                res.set_item("z", z).unwrap();
+            }
+        }
+        res.into_py(py)
+    }
+}
+
+/// The parts of a command that can be changed after it was issued
+#[derive(Clone, Debug)]
+struct CommandState {
+    type_: u8,
+    target: CommandTarget,
+    count: i32,
+}
+
+#[derive(Debug)]
+struct CommandRecord {
+    source: u8,
+    factory: bool,
+    blueprint: Option<String>,
+    /// Every change to the command along with the tick it happened on
+    states: Vec<(u32, CommandState)>,
+    /// Queue key and index of each entry for the command in `CommandQueueTracker::queues`
+    entries: Vec<((u32, bool), usize)>,
+}
+
+impl CommandRecord {
+    fn state_at(&self, tick: u32) -> &CommandState {
+        self.states
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= tick)
+            .map_or(&self.states[0].1, |(_, state)| state)
+    }
+
+    fn update(&mut self, tick: u32, f: impl FnOnce(&mut CommandState)) {
+        let mut state = self.states.last().unwrap().1.clone();
+        f(&mut state);
+        self.states.push((tick, state));
+    }
+}
+
+/// The time a command spent in the queue of one unit
+#[derive(Debug)]
+struct QueueEntry {
+    id: u32,
+    record: usize,
+    start: u32,
+    end: Option<u32>,
+}
+
+/// Rebuilds the order queues of units from the commands referencing command ids.
+///
+/// Only orders given by players are recorded in the replay, so commands that complete in the sim
+/// (a move that reaches its destination, a unit that finished building) stay in the queue until
+/// the unit is given new orders with the queue cleared or the command is removed explicitly.
+/// Most commands don't record whether they were queued up or replaced the existing queue. Build
+/// orders for factories and silos are assumed to be added to the queue and all other commands to
+/// replace it.
+#[pyclass(module = "fafreplay")]
+#[derive(Default)]
+pub struct CommandQueueTracker {
+    /// Command ids can be reused, so every issued command gets its own record
+    commands: Vec<CommandRecord>,
+    /// Index into `commands` of the last command issued with an id
+    ids: HashMap<u32, usize>,
+    /// Queues by unit id. Factories have a second queue with the orders for the units they
+    /// build, which is keyed with `true`.
+    queues: HashMap<(u32, bool), Vec<QueueEntry>>,
+}
+
+#[pymethods]
+impl CommandQueueTracker {
+    /// parse(data: bytes) -> CommandQueueTracker
+    ///
+    /// Parse a replay and rebuild the order queues of all units.
+    #[staticmethod]
+    #[pyo3(text_signature = "(data)")]
+    fn parse(py: Python, data: &PyBytes) -> PyResult<CommandQueueTracker> {
+        let mut bytes = data.as_bytes();
+        let parser = queue_parser();
+
+        let (replay, tracker) = py.allow_threads(|| {
+            let tracker = RefCell::new(CommandQueueTracker::default());
+            let replay = parser.parse_with_callback(&mut bytes, |sim, command| {
+                tracker
+                    .borrow_mut()
+                    .process(sim.tick, sim.command_source, command);
+                process_command(sim, command)
+            });
+            (replay, tracker.into_inner())
+        });
+        convert_result(replay)?;
+
+        Ok(tracker)
+    }
+
+    /// Ids of all units that were given orders, in ascending order
+    #[getter]
+    fn units(&self) -> Vec<u32> {
+        let mut units: Vec<u32> = self.queues.keys().map(|(unit, _)| *unit).collect();
+        units.sort_unstable();
+        units.dedup();
+        units
+    }
+
+    /// queue(unit: int, tick: int) -> List[QueuedCommand]
+    ///
+    /// The order queue of `unit` at `tick`, starting with the command that is being executed.
+    #[pyo3(text_signature = "(unit, tick)")]
+    fn queue(&self, unit: u32, tick: u32) -> Vec<QueuedCommand> {
+        self.queue_at(unit, false, tick)
+    }
+
+    /// factory_queue(unit: int, tick: int) -> List[QueuedCommand]
+    ///
+    /// The orders given to units leaving the factory `unit` at `tick`, such as rally points.
+    #[pyo3(text_signature = "(unit, tick)")]
+    fn factory_queue(&self, unit: u32, tick: u32) -> Vec<QueuedCommand> {
+        self.queue_at(unit, true, tick)
+    }
+
+    /// current(unit: int, tick: int) -> Optional[QueuedCommand]
+    ///
+    /// The command `unit` was executing at `tick`, or `None` if it had no orders.
+    #[pyo3(text_signature = "(unit, tick)")]
+    fn current(&self, unit: u32, tick: u32) -> Option<QueuedCommand> {
+        self.queue(unit, tick).into_iter().next()
+    }
+
+    /// history(unit: int) -> List[QueuedCommand]
+    ///
+    /// Every command that was ever in one of the queues of `unit` in the order they were issued.
+    /// Values that changed over time are the last known ones.
+    #[pyo3(text_signature = "(unit)")]
+    fn history(&self, unit: u32) -> Vec<QueuedCommand> {
+        let mut entries: Vec<&QueueEntry> = [false, true]
+            .iter()
+            .filter_map(|factory| self.queues.get(&(unit, *factory)))
+            .flatten()
+            .collect();
+        entries.sort_by_key(|e| e.record);
+
+        entries
+            .into_iter()
+            .map(|e| self.queued_command(unit, e, u32::MAX))
+            .collect()
+    }
+
+    /// Ids of all units that were given factory build orders, in ascending order
+    #[getter]
+    fn factories(&self) -> Vec<u32> {
+        let mut factories: Vec<u32> = self
+            .queues
+            .iter()
+            .filter(|((_, factory), queue)| !factory && queue.iter().any(|e| self.is_production(e)))
+            .map(|((unit, _), _)| *unit)
+            .collect();
+        factories.sort_unstable();
+        factories
+    }
+
+    /// production(factory: Optional[int] = None) -> List[ProductionEvent]
+    ///
+    /// The production queue timeline of `factory`, or of all factories if it is `None`. Every
+    /// build order creates a `queued` event, followed by a `count` event whenever its count is
+    /// changed and a `removed` event once it leaves the queue. Events are ordered by tick.
+    #[pyo3(text_signature = "(factory=None)")]
+    fn production(&self, factory: Option<u32>) -> Vec<ProductionEvent> {
+        let mut events = Vec::new();
+        for ((unit, is_factory), queue) in self.queues.iter() {
+            if *is_factory || factory.is_some_and(|f| f != *unit) {
+                continue;
+            }
+            for entry in queue.iter().filter(|e| self.is_production(e)) {
+                self.production_events(*unit, entry, &mut events);
+            }
+        }
+        events.sort_by_key(|e| (e.tick, e.factory, e.record, e.kind));
+
+        events
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "CommandQueueTracker(units={}, commands={})",
+            self.units().len(),
+            self.commands.len()
+        )
+    }
+}
+
+impl CommandQueueTracker {
+    /// Update the queues with a command that was sent at `tick` by `source`
+    fn process(&mut self, tick: u32, source: u8, command: &ReplayCommand) {
+        use ReplayCommand::*;
+
+        match command {
+            IssueCommand(game_command) => self.issue(tick, source, game_command, false),
+            IssueFactoryCommand(game_command) => self.issue(tick, source, game_command, true),
+            IncreaseCommandCount { id, delta } => self.change_count(tick, *id, *delta),
+            DecreaseCommandCount { id, delta } => {
+                self.change_count(tick, *id, delta.saturating_neg())
+            }
+            SetCommandTarget { id, target } => {
+                if let Some(record) = self.record_mut(*id) {
+                    record.update(tick, |state| state.target = target.into());
+                }
+            }
+            SetCommandType { id, type_ } => {
+                if let Some(record) = self.record_mut(*id) {
+                    record.update(tick, |state| state.type_ = *type_);
+                }
+            }
+            RemoveCommandFromQueue { id, unit } => self.remove(tick, *id, *unit),
+            _ => {}
+        }
+    }
+
+    fn issue(&mut self, tick: u32, source: u8, game_command: &GameCommand, factory: bool) {
+        let record = self.commands.len();
+        self.ids.insert(game_command.id, record);
+        self.commands.push(CommandRecord {
+            source,
+            factory,
+            blueprint: Some(game_command.blueprint.clone()).filter(|b| !b.is_empty()),
+            states: vec![(
+                tick,
+                CommandState {
+                    type_: game_command.type_,
+                    target: (&game_command.target).into(),
+                    count: 1,
+                },
+            )],
+            entries: Vec::new(),
+        });
+
+        for unit in game_command.entity_ids.iter() {
+            let key = (*unit, factory);
+            let queue = self.queues.entry(key).or_default();
+            if game_command
+                .clear_queue
+                .unwrap_or(!appends(game_command.type_))
+            {
+                for entry in queue.iter_mut().filter(|e| e.end.is_none()) {
+                    entry.end = Some(tick);
+                }
+            }
+            self.commands[record].entries.push((key, queue.len()));
+            queue.push(QueueEntry {
+                id: game_command.id,
+                record,
+                start: tick,
+                end: None,
+            });
+        }
+    }
+
+    /// Factory commands are removed once their count drops to 0
+    fn change_count(&mut self, tick: u32, id: u32, delta: i32) {
+        let index = match self.ids.get(&id) {
+            Some(index) => *index,
+            None => return,
+        };
+        let record = &mut self.commands[index];
+        record.update(tick, |state| {
+            state.count = state.count.saturating_add(delta)
+        });
+        if record.states.last().unwrap().1.count > 0 {
+            return;
+        }
+
+        for (key, position) in record.entries.iter() {
+            let entry = &mut self.queues.get_mut(key).unwrap()[*position];
+            if entry.end.is_none() {
+                entry.end = Some(tick);
+            }
+        }
+    }
+
+    fn record_mut(&mut self, id: u32) -> Option<&mut CommandRecord> {
+        let index = *self.ids.get(&id)?;
+        self.commands.get_mut(index)
+    }
+
+    fn remove(&mut self, tick: u32, id: u32, unit: u32) {
+        for factory in [false, true] {
+            if let Some(queue) = self.queues.get_mut(&(unit, factory)) {
+                for entry in queue.iter_mut().filter(|e| e.id == id && e.end.is_none()) {
+                    entry.end = Some(tick);
+                }
+            }
+        }
+    }
+
+    /// Whether an entry of a unit queue is a factory build order
+    fn is_production(&self, entry: &QueueEntry) -> bool {
+        let record = &self.commands[entry.record];
+        record.states[0].1.type_ == game_command::BUILD_FACTORY
+    }
+
+    fn production_events(&self, unit: u32, entry: &QueueEntry, events: &mut Vec<ProductionEvent>) {
+        let record = &self.commands[entry.record];
+        let event = |kind, tick, count| ProductionEvent {
+            tick,
+            factory: unit,
+            source: record.source,
+            id: entry.id,
+            kind,
+            blueprint: record.blueprint.clone(),
+            count,
+            record: entry.record,
+        };
+
+        let (_, initial) = &record.states[0];
+        events.push(event(EventKind::Queued, entry.start, initial.count));
+
+        let mut count = initial.count;
+        for (tick, state) in record.states.iter().skip(1) {
+            if entry.end.is_some_and(|end| *tick > end) {
+                break;
+            }
+            if state.count != count {
+                count = state.count;
+                events.push(event(EventKind::Count, *tick, count));
+            }
+        }
+
+        if let Some(end) = entry.end {
+            events.push(event(EventKind::Removed, end, 0));
+        }
+    }
+
+    fn queue_at(&self, unit: u32, factory: bool, tick: u32) -> Vec<QueuedCommand> {
+        self.queues
+            .get(&(unit, factory))
+            .into_iter()
+            .flatten()
+            .filter(|e| e.start <= tick && e.end.is_none_or(|end| end > tick))
+            .map(|e| self.queued_command(unit, e, tick))
+            .collect()
+    }
+
+    fn queued_command(&self, unit: u32, entry: &QueueEntry, tick: u32) -> QueuedCommand {
+        let record = &self.commands[entry.record];
+        let state = record.state_at(tick);
+
+        QueuedCommand {
+            unit,
+            id: entry.id,
+            source: record.source,
+            factory: record.factory,
+            type_: state.type_,
+            target: state.target.clone(),
+            blueprint: record.blueprint.clone(),
+            count: state.count,
+            issued_tick: entry.start,
+            removed_tick: entry.end,
+        }
+    }
+}
+
+/// Whether a command type is added to the queue when the replay doesn't say otherwise
+fn appends(type_: u8) -> bool {
+    matches!(
+        type_,
+        game_command::BUILD_FACTORY
+            | game_command::BUILD_SILO_TACTICAL
+            | game_command::BUILD_SILO_NUKE
+    )
+}
+
+fn queue_parser() -> Parser {
+    ParserBuilder::new()
+        .commands(&QUEUE_COMMANDS)
+        .save_commands(false)
+        .stop_on_desync(false)
+        .build()
+}
+
+/// A command in the order queue of a unit
+#[pyclass(module = "fafreplay")]
+pub struct QueuedCommand {
+    #[pyo3(get)]
+    pub unit: u32,
+    /// The command id
+    #[pyo3(get)]
+    pub id: u32,
+    /// Command source of the player that issued the command
+    #[pyo3(get)]
+    pub source: u8,
+    /// Whether the command was issued with `IssueFactoryCommand`
+    #[pyo3(get)]
+    pub factory: bool,
+    type_: u8,
+    target: CommandTarget,
+    #[pyo3(get)]
+    pub blueprint: Option<String>,
+    /// Number of repetitions for factory commands
+    #[pyo3(get)]
+    pub count: i32,
+    #[pyo3(get)]
+    pub issued_tick: u32,
+    /// The tick the command was removed from the queue, or `None` if it never was
+    #[pyo3(get)]
+    pub removed_tick: Option<u32>,
+}
+
+#[pymethods]
+impl QueuedCommand {
+    /// The command type as a `UnitCommandType`
+    #[getter(r#type)]
+    fn type_(&self, py: Python) -> PyObject {
+        unit_command_type(py).member(self.type_, py)
+    }
+
+    /// Same as the `target` of parsed commands
+    #[getter]
+    fn target(&self, py: Python) -> PyObject {
+        self.target.clone().into_py(py)
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "QueuedCommand(unit={}, id={}, type={}, issued_tick={})",
+            self.unit,
+            self.id,
+            self.type_(py).getattr(py, "name")?,
+            self.issued_tick
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum EventKind {
+    Queued,
+    Count,
+    Removed,
+}
+
+impl EventKind {
+    fn name(&self) -> &'static str {
+        match self {
+            EventKind::Queued => "queued",
+            EventKind::Count => "count",
+            EventKind::Removed => "removed",
+        }
+    }
+}
+
+/// A change to the production queue of a factory
+#[pyclass(module = "fafreplay")]
+pub struct ProductionEvent {
+    #[pyo3(get)]
+    pub tick: u32,
+    /// Unit id of the factory
+    #[pyo3(get)]
+    pub factory: u32,
+    /// Command source of the player that gave the order
+    #[pyo3(get)]
+    pub source: u8,
+    /// The command id of the build order
+    #[pyo3(get)]
+    pub id: u32,
+    kind: EventKind,
+    #[pyo3(get)]
+    pub blueprint: Option<String>,
+    /// Number of units left in the build order after the event. Always 0 for `removed`.
+    #[pyo3(get)]
+    pub count: i32,
+    /// Keeps events of reused command ids apart when sorting
+    record: usize,
+}
+
+#[pymethods]
+impl ProductionEvent {
+    /// One of `queued`, `count` or `removed`
+    #[getter]
+    fn event(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ProductionEvent(tick={}, factory={}, event={:?}, blueprint={}, count={})",
+            self.tick,
+            self.factory,
+            self.kind.name(),
+            repr(&self.blueprint.as_ref().map(|s| format!("{:?}", s))),
+            self.count
+        )
+    }
+}
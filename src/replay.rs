@@ -1,22 +1,32 @@
-use crate::lua::{table_into_py, LuaObject};
+use crate::columns::command_id;
+use crate::constants::{command_type, unit_command_type};
+use crate::fields::{visit_command, FieldVisitor};
+use crate::lua::{LuaObject, LuaObjectRef, LuaTableProxy};
+use crate::map::MapInfo;
+use crate::mods::mod_info;
+use crate::options::GameOptions;
+use crate::player::player_info;
+use crate::teams::Teams;
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyDict};
-use faf_replay_parser::scfa::replay::GameCommand;
 
-pub struct Replay(pub faf_replay_parser::scfa::replay::Replay);
-pub struct ReplayHeader(pub faf_replay_parser::scfa::replay::ReplayHeader);
+/// A replay and whether the header Lua tables are converted lazily, see `ReplayHeader`
+pub struct Replay(pub faf_replay_parser::scfa::replay::Replay, pub bool);
+/// A header and whether its Lua tables are converted lazily. Lazy headers hold `LuaTable`
+/// proxies instead of dicts. The fields derived from the tables are included either way.
+pub struct ReplayHeader(pub faf_replay_parser::scfa::replay::ReplayHeader, pub bool);
 pub struct ReplayBody(pub faf_replay_parser::scfa::replay::ReplayBody);
 pub struct SimData(pub faf_replay_parser::scfa::replay::SimData);
 pub struct ReplayCommand(pub faf_replay_parser::scfa::replay::ReplayCommand);
-pub struct Position(pub faf_replay_parser::scfa::replay::Position);
-pub struct Target(pub faf_replay_parser::scfa::replay::Target);
-pub struct Formation(pub faf_replay_parser::scfa::replay::Formation);
+pub struct Position<'a>(pub &'a faf_replay_parser::scfa::replay::Position);
+pub struct Target<'a>(pub &'a faf_replay_parser::scfa::replay::Target);
+pub struct Formation<'a>(pub &'a faf_replay_parser::scfa::replay::Formation);
 
 impl IntoPy<PyObject> for Replay {
     fn into_py(self, py: Python) -> PyObject {
         let res = PyDict::new(py);
 
-        res.set_item::<&str, PyObject>("header", ReplayHeader(self.0.header).into_py(py))
+        res.set_item::<&str, PyObject>("header", ReplayHeader(self.0.header, self.1).into_py(py))
             .unwrap();
         res.set_item::<&str, PyObject>("body", ReplayBody(self.0.body).into_py(py))
             .unwrap();
@@ -27,22 +37,49 @@ impl IntoPy<PyObject> for Replay {
 
 impl IntoPy<PyObject> for ReplayHeader {
     fn into_py(self, py: Python) -> PyObject {
+        let lazy = self.1;
         let res = PyDict::new(py);
+        let players = player_info(&self.0.armies);
+        let teams = Teams::new(&players, &self.0.players);
+        let lua = |obj| {
+            if lazy {
+                LuaTableProxy::new_py(obj, py).unwrap()
+            } else {
+                LuaObject(obj).into_py(py)
+            }
+        };
 
         res.set_item::<&str, String>("scfa_version", self.0.scfa_version)
             .unwrap();
         res.set_item("replay_version", self.0.replay_version).unwrap();
+        res.set_item::<&str, PyObject>(
+            "map_info",
+            MapInfo::new(&self.0.map_file, &self.0.scenario).into_py(py),
+        )
+        .unwrap();
         res.set_item("map_file", self.0.map_file).unwrap();
-        res.set_item::<&str, PyObject>("mods", LuaObject(self.0.mods).into_py(py))
-            .unwrap();
-        res.set_item::<&str, PyObject>("scenario", LuaObject(self.0.scenario).into_py(py))
+        res.set_item::<&str, PyObject>("mod_info", mod_info(&self.0.mods).into_py(py))
             .unwrap();
+        res.set_item("mods", lua(self.0.mods)).unwrap();
+        res.set_item::<&str, PyObject>(
+            "game_options",
+            GameOptions::from_scenario(&self.0.scenario, py).into_py(py),
+        )
+        .unwrap();
+        res.set_item("scenario", lua(self.0.scenario)).unwrap();
         res.set_item::<&str, PyObject>("players", self.0.players.into_py(py))
             .unwrap();
         res.set_item("cheats_enabled", self.0.cheats_enabled).unwrap();
         res.set_item("army_count", self.0.army_count).unwrap();
-        res.set_item::<&str, PyObject>("armies", table_into_py(self.0.armies, py))
+        res.set_item::<&str, PyObject>("teams", teams.into_py(py))
             .unwrap();
+        res.set_item::<&str, PyObject>("player_info", players.into_py(py))
+            .unwrap();
+        let armies = PyDict::new(py);
+        for (source, army) in self.0.armies {
+            armies.set_item(source, lua(army)).unwrap();
+        }
+        res.set_item("armies", armies).unwrap();
         res.set_item("seed", self.0.seed).unwrap();
 
         res.into_py(py)
@@ -83,144 +120,94 @@ impl IntoPy<PyObject> for SimData {
 
 impl IntoPy<PyObject> for ReplayCommand {
     fn into_py(self, py: Python) -> PyObject {
-        use faf_replay_parser::scfa::replay::ReplayCommand::*;
-        let res = PyDict::new(py);
+        command_to_py(&self.0, py)
+    }
+}
 
-        match self.0 {
-            Advance { ticks } => {
-                res.set_item("name", "Advance").unwrap();
-                res.set_item("ticks", ticks).unwrap();
-            }
-            SetCommandSource { id } => {
-                res.set_item("name", "SetCommandSource").unwrap();
-                res.set_item("id", id).unwrap();
-            }
-            CommandSourceTerminated => res.set_item("name", "CommandSourceTerminated").unwrap(),
-            VerifyChecksum { digest, tick } => {
-                res.set_item("name", "VerifyChecksum").unwrap();
-                res.set_item("digest", PyBytes::new(py, &digest)).unwrap();
-                res.set_item("tick", tick).unwrap();
-            }
-            RequestPause => res.set_item("name", "RequestPause").unwrap(),
-            Resume => res.set_item("name", "Resume").unwrap(),
-            SingleStep => res.set_item("name", "SingleStep").unwrap(),
-            CreateUnit { army, blueprint, x, z, heading } => {
-                res.set_item("name", "CreateUnit").unwrap();
-                res.set_item("army", army).unwrap();
-                res.set_item("blueprint", blueprint).unwrap();
-                res.set_item("x", x).unwrap();
-                res.set_item("z", z).unwrap();
-                res.set_item("heading", heading).unwrap();
-            }
-            CreateProp { blueprint, position } => {
-                res.set_item("name", "CreateProp").unwrap();
-                res.set_item("blueprint", blueprint).unwrap();
-                res.set_item::<&str, PyObject>("position", Position(position).into_py(py))
-                    .unwrap();
-            }
-            DestroyEntity { unit } => {
-                res.set_item("name", "DestroyEntity").unwrap();
-                res.set_item("unit", unit).unwrap();
-            }
-            WarpEntity { unit, x, y, z } => {
-                res.set_item("name", "WarpEntity").unwrap();
-                res.set_item("unit", unit).unwrap();
-                res.set_item("x", x).unwrap();
-                res.set_item("y", y).unwrap();
-                res.set_item("z", z).unwrap();
-            }
-            ProcessInfoPair { unit, arg1, arg2 } => {
-                res.set_item("name", "ProcessInfoPair").unwrap();
-                res.set_item("unit", unit).unwrap();
-                res.set_item("arg1", arg1).unwrap();
-                res.set_item("arg2", arg2).unwrap();
-            }
-            IssueCommand(game_command) => {
-                res.set_item("name", "IssueCommand").unwrap();
-                set_game_command_items(res, game_command, py);
-            }
-            IssueFactoryCommand(game_command) => {
-                res.set_item("name", "IssueFactoryCommand").unwrap();
-                set_game_command_items(res, game_command, py);
-            }
-            IncreaseCommandCount { id, delta } => {
-                res.set_item("name", "IncreaseCommandCount").unwrap();
-                res.set_item("id", id).unwrap();
-                res.set_item("delta", delta).unwrap();
-            }
-            DecreaseCommandCount { id, delta } => {
-                res.set_item("name", "DecreaseCommandCount").unwrap();
-                res.set_item("id", id).unwrap();
-                res.set_item("delta", delta).unwrap();
-            }
-            SetCommandTarget { id, target } => {
-                res.set_item("name", "SetCommandTarget").unwrap();
-                res.set_item("id", id).unwrap();
-                res.set_item::<&str, PyObject>("target", Target(target).into_py(py))
-                    .unwrap();
-            }
-            SetCommandType { id, type_ } => {
-                res.set_item("name", "SetCommandType").unwrap();
-                res.set_item("id", id).unwrap();
-                res.set_item("type_", type_).unwrap();
-            }
-            SetCommandCells { id, cells, position } => {
-                res.set_item("name", "SetCommandCells").unwrap();
-                res.set_item("id", id).unwrap();
-                res.set_item::<&str, PyObject>("cells", LuaObject(cells).into_py(py))
-                    .unwrap();
-                res.set_item::<&str, PyObject>("position", Position(position).into_py(py))
-                    .unwrap();
-            }
-            RemoveCommandFromQueue { id, unit } => {
-                res.set_item("name", "RemoveCommandFromQueue").unwrap();
-                res.set_item("id", id).unwrap();
-                res.set_item("unit", unit).unwrap();
-            }
-            DebugCommand { command, position, focus_army, selection } => {
-                res.set_item("name", "DebugCommand").unwrap();
-                res.set_item("command", command).unwrap();
-                res.set_item::<&str, PyObject>("position", Position(position).into_py(py))
-                    .unwrap();
-                res.set_item("focus_army", focus_army).unwrap();
-                res.set_item("selection", selection).unwrap();
-            }
-            ExecuteLuaInSim { code } => {
-                res.set_item("name", "ExecuteLuaInSim").unwrap();
-                res.set_item("code", code).unwrap();
-            }
-            LuaSimCallback { func, args, selection } => {
-                res.set_item("name", "LuaSimCallback").unwrap();
-                res.set_item("func", func).unwrap();
-                res.set_item::<&str, PyObject>("args", LuaObject(args).into_py(py))
-                    .unwrap();
-                res.set_item("selection", selection).unwrap();
-            }
-            EndGame => res.set_item("name", "EndGame").unwrap(),
-        }
+/// Convert a command to the same dictionary as `ReplayCommand` without taking ownership of it.
+pub fn command_to_py(
+    command: &faf_replay_parser::scfa::replay::ReplayCommand,
+    py: Python,
+) -> PyObject {
+    let res = PyDict::new(py);
+    res.set_item("name", command_type(py).member(command_id(command), py))
+        .unwrap();
 
-        res.into_py(py)
+    visit_command(command, &mut DictFields(res));
+
+    res.into_py(py)
+}
+
+/// Sets each field of a command as an item of the dictionary.
+struct DictFields<'p>(&'p PyDict);
+
+impl DictFields<'_> {
+    fn set(&self, name: &str, value: impl ToPyObject) {
+        self.0.set_item(name, value).unwrap();
     }
 }
 
-fn set_game_command_items(res: &PyDict, game_command: GameCommand, py: Python) {
-    res.set_item("entity_ids", game_command.entity_ids).unwrap();
-    res.set_item("id", game_command.id).unwrap();
-    res.set_item("coordinated_attack_cmd_id", game_command.coordinated_attack_cmd_id).unwrap();
-    res.set_item("type", game_command.type_).unwrap();
-    res.set_item("arg2", game_command.arg2).unwrap();
-    res.set_item::<&str, PyObject>("target", Target(game_command.target).into_py(py)).unwrap();
-    res.set_item("arg3", game_command.arg3).unwrap();
-    res.set_item::<&str, PyObject>("formation", game_command.formation.map(|f| Formation(f)).into_py(py)).unwrap();
-    res.set_item("blueprint", game_command.blueprint).unwrap();
-    res.set_item("arg4", game_command.arg4).unwrap();
-    res.set_item("arg5", game_command.arg5).unwrap();
-    res.set_item("arg6", game_command.arg6).unwrap();
-    res.set_item::<&str, PyObject>("upgrades", LuaObject(game_command.upgrades).into_py(py)).unwrap();
-    res.set_item("clear_queue", game_command.clear_queue).unwrap();
+impl FieldVisitor for DictFields<'_> {
+    fn u8(&mut self, name: &'static str, value: u8) {
+        self.set(name, value);
+    }
+
+    fn u32(&mut self, name: &'static str, value: u32) {
+        self.set(name, value);
+    }
+
+    fn i32(&mut self, name: &'static str, value: i32) {
+        self.set(name, value);
+    }
+
+    fn f32(&mut self, name: &'static str, value: f32) {
+        self.set(name, value);
+    }
+
+    fn bool(&mut self, name: &'static str, value: Option<bool>) {
+        self.set(name, value);
+    }
+
+    fn string(&mut self, name: &'static str, value: &str) {
+        self.set(name, value);
+    }
+
+    fn bytes(&mut self, name: &'static str, value: &[u8]) {
+        // Bytes are copied
+        self.set(name, PyBytes::new(self.0.py(), value));
+    }
+
+    fn ids(&mut self, name: &'static str, value: &[u32]) {
+        self.set(name, value);
+    }
+
+    fn position(&mut self, name: &'static str, value: &faf_replay_parser::scfa::replay::Position) {
+        self.set(name, Position(value).into_py(self.0.py()));
+    }
+
+    fn target(&mut self, name: &'static str, value: &faf_replay_parser::scfa::replay::Target) {
+        self.set(name, Target(value).into_py(self.0.py()));
+    }
+
+    fn formation(
+        &mut self,
+        name: &'static str,
+        value: Option<&faf_replay_parser::scfa::replay::Formation>,
+    ) {
+        self.set(name, value.map(Formation).into_py(self.0.py()));
+    }
+
+    fn lua(&mut self, name: &'static str, value: &faf_replay_parser::lua::LuaObject) {
+        self.set(name, LuaObjectRef(value).into_py(self.0.py()));
+    }
+
+    fn unit_command_type(&mut self, name: &'static str, value: u8) {
+        let py = self.0.py();
+        self.set(name, unit_command_type(py).member(value, py));
+    }
 }
 
-impl IntoPy<PyObject> for Position {
+impl IntoPy<PyObject> for Position<'_> {
     fn into_py(self, py: Python) -> PyObject {
         let res = PyDict::new(py);
 
@@ -232,7 +219,7 @@ impl IntoPy<PyObject> for Position {
     }
 }
 
-impl IntoPy<PyObject> for Target {
+impl IntoPy<PyObject> for Target<'_> {
     fn into_py(self, py: Python) -> PyObject {
         use faf_replay_parser::scfa::replay::Target::*;
 
@@ -240,7 +227,7 @@ impl IntoPy<PyObject> for Target {
             None => py.None(),
             Entity { id } => {
                 let res = PyDict::new(py);
-                res.set_item("id", id).unwrap();
+                res.set_item("id", *id).unwrap();
                 res.into_py(py)
             }
             Position(p) => self::Position(p).into_py(py),
@@ -248,7 +235,7 @@ impl IntoPy<PyObject> for Target {
     }
 }
 
-impl IntoPy<PyObject> for Formation {
+impl IntoPy<PyObject> for Formation<'_> {
     fn into_py(self, py: Python) -> PyObject {
         let res = PyDict::new(py);
 
@@ -0,0 +1,99 @@
+use faf_replay_parser::scfa::replay::{replay_command, ReplayCommand, SimData};
+use faf_replay_parser::{ReplayReadError, ReplayResult};
+
+/// Updates `sim` in the same way as the upstream parser does when no custom callback is given.
+///
+/// The upstream function is private, so any code using `parse_with_callback` needs to call this
+/// first in order to keep the tick counter and desync detection working.
+pub fn process_command(sim: &mut SimData, command: &ReplayCommand) -> ReplayResult<()> {
+    use ReplayCommand::*;
+
+    match command {
+        Advance { ticks } => sim.tick += *ticks,
+        SetCommandSource { id } => sim.command_source = *id,
+        CommandSourceTerminated => {
+            sim.players_last_tick.insert(sim.command_source, sim.tick);
+        }
+        VerifyChecksum { digest, tick } => {
+            if sim.checksum_tick < Some(*tick) {
+                // First checksum submitted for this tick
+                sim.checksum_tick = Some(*tick);
+                sim.checksum.copy_from_slice(&digest[..16]);
+                return Ok(());
+            }
+
+            if sim.checksum != digest[..16] {
+                if sim.desync_tick.is_none() {
+                    sim.desync_tick = Some(sim.tick);
+                    sim.desync_ticks = Some(vec![]);
+                }
+
+                sim.desync_ticks.as_mut().unwrap().push(sim.tick);
+
+                return Err(ReplayReadError::Desynced(sim.tick));
+            }
+        }
+        _ => (),
+    };
+    Ok(())
+}
+
+/// Number of commands of each type, indexed by command id
+pub type CommandCounts = [u32; replay_command::MAX as usize + 1];
+
+/// Scan a replay body without parsing most commands, similar to `scfa::body_ticks`.
+///
+/// Only the commands that update `SimData` are decoded, every other command is skipped and
+/// counted by its type. Desyncs don't stop the scan and an incomplete command at the end of the
+/// data is ignored.
+pub fn scan_body(data: &[u8]) -> ReplayResult<(SimData, CommandCounts)> {
+    use ReplayCommand::*;
+
+    let mut sim = SimData::new();
+    let mut counts = [0; replay_command::MAX as usize + 1];
+    let mut curr = 0;
+
+    while curr + 3 <= data.len() {
+        let command_id = data[curr];
+        if command_id > replay_command::MAX {
+            return Err(ReplayReadError::Malformed("invalid command"));
+        }
+        let size = u16::from_le_bytes([data[curr + 1], data[curr + 2]]) as usize;
+        if size < 3 {
+            return Err(ReplayReadError::Malformed("invalid command size"));
+        }
+        let frame = match data.get(curr + 3..curr + size) {
+            Some(frame) => frame,
+            None => break,
+        };
+        curr += size;
+        counts[command_id as usize] += 1;
+
+        let command = match (command_id, frame.len()) {
+            (replay_command::ADVANCE, 4) => Advance {
+                ticks: u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]),
+            },
+            (replay_command::SET_COMMAND_SOURCE, 1) => SetCommandSource { id: frame[0] },
+            (replay_command::COMMAND_SOURCE_TERMINATED, 0) => CommandSourceTerminated,
+            (replay_command::VERIFY_CHECKSUM, 20) => VerifyChecksum {
+                digest: frame[..16].to_vec(),
+                tick: u32::from_le_bytes([frame[16], frame[17], frame[18], frame[19]]),
+            },
+            (
+                replay_command::ADVANCE
+                | replay_command::SET_COMMAND_SOURCE
+                | replay_command::COMMAND_SOURCE_TERMINATED
+                | replay_command::VERIFY_CHECKSUM,
+                _,
+            ) => return Err(ReplayReadError::Malformed("invalid command size")),
+            _ => continue,
+        };
+
+        match process_command(&mut sim, &command) {
+            Err(ReplayReadError::Desynced(_)) => {}
+            result => result?,
+        }
+    }
+
+    Ok((sim, counts))
+}
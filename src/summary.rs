@@ -0,0 +1,181 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::columns::command_id;
+use crate::constants::command_type;
+use crate::convert_result;
+use crate::map::MapInfo;
+use crate::pauses::PauseTimeline;
+use crate::player::{player_info, PlayerInfo};
+use crate::sim::process_command;
+use crate::teams::Teams;
+
+use faf_replay_parser::scfa::replay::{replay_command, ReplayCommand};
+use faf_replay_parser::scfa::ParserBuilder;
+
+/// Commands that are counted as actions for the APM
+static ACTION_COMMANDS: [u8; 8] = [
+    replay_command::ISSUE_COMMAND,
+    replay_command::ISSUE_FACTORY_COMMAND,
+    replay_command::INCREASE_COMMAND_COUNT,
+    replay_command::DECREASE_COMMAND_COUNT,
+    replay_command::SET_COMMAND_TARGET,
+    replay_command::SET_COMMAND_TYPE,
+    replay_command::SET_COMMAND_CELLS,
+    replay_command::REMOVE_COMMAND_FROM_QUEUE,
+];
+
+const TICKS_PER_MINUTE: f32 = 600.0;
+
+/// An overview of a replay collected in a single pass
+#[pyclass(module = "fafreplay")]
+pub struct Summary {
+    /// Length of the game in ticks
+    #[pyo3(get)]
+    pub ticks: u32,
+    #[pyo3(get)]
+    pub map: Py<MapInfo>,
+    /// Players ordered by start spot, see `header["player_info"]`
+    #[pyo3(get)]
+    pub players: Vec<Py<PlayerInfo>>,
+    #[pyo3(get)]
+    pub teams: Py<Teams>,
+    /// Ticks on which the checksums didn't match, or `None` if the replay didn't desync
+    #[pyo3(get)]
+    pub desync_ticks: Option<Vec<u32>>,
+    /// Actions per minute by command source. Actions are the commands that give orders to units
+    /// or change their command queues, averaged over the time until the player left the game.
+    #[pyo3(get)]
+    pub apm: HashMap<u8, f32>,
+    /// Tick on which each command source left the game
+    #[pyo3(get)]
+    pub quit_ticks: HashMap<u8, u32>,
+    /// Number of times the game was paused
+    #[pyo3(get)]
+    pub pauses: usize,
+    /// Number of commands of each `CommandType` by command source. `Advance` and
+    /// `SetCommandSource` are not counted as they aren't sent by any particular player.
+    #[pyo3(get)]
+    pub command_counts: PyObject,
+}
+
+#[pymethods]
+impl Summary {
+    /// Length of the game in seconds
+    #[getter]
+    fn duration(&self) -> f64 {
+        self.ticks as f64 / 10.0
+    }
+
+    #[getter]
+    fn desynced(&self) -> bool {
+        self.desync_ticks.is_some()
+    }
+
+    fn __repr__(&self, py: Python) -> String {
+        format!(
+            "Summary(map={:?}, players={}, ticks={}, desynced={})",
+            self.map.borrow(py).name.as_deref().unwrap_or(""),
+            self.players.len(),
+            self.ticks,
+            if self.desynced() { "True" } else { "False" }
+        )
+    }
+}
+
+#[derive(Default)]
+struct SummaryState {
+    actions: HashMap<u8, u32>,
+    counts: HashMap<u8, HashMap<u8, u32>>,
+    pauses: PauseTimeline,
+}
+
+impl SummaryState {
+    fn process(&mut self, tick: u32, source: u8, command: &ReplayCommand) {
+        self.pauses.process(tick, source, command);
+
+        let id = command_id(command);
+        if id == replay_command::ADVANCE || id == replay_command::SET_COMMAND_SOURCE {
+            return;
+        }
+        let counts = self.counts.entry(source).or_default();
+        *counts.entry(id).or_default() += 1;
+        if ACTION_COMMANDS.contains(&id) {
+            *self.actions.entry(source).or_default() += 1;
+        }
+    }
+}
+
+/// summarize(data: bytes) -> Summary
+///
+/// Parse a replay once and collect the most commonly needed information: duration, map,
+/// players and teams, desyncs, APM, when players left, pauses and command counts.
+#[pyfunction]
+#[pyo3(text_signature = "(data)")]
+pub fn summarize(py: Python, data: &PyBytes) -> PyResult<Summary> {
+    let mut bytes = data.as_bytes();
+    let commands: Vec<u8> = (0..=replay_command::MAX).collect();
+    let parser = ParserBuilder::new()
+        .commands(&commands)
+        .save_commands(false)
+        .stop_on_desync(false)
+        .build();
+
+    let (replay, state) = py.allow_threads(|| {
+        let state = RefCell::new(SummaryState::default());
+        let replay = parser.parse_with_callback(&mut bytes, |sim, command| {
+            state
+                .borrow_mut()
+                .process(sim.tick, sim.command_source, command);
+            process_command(sim, command)
+        });
+        (replay, state.into_inner())
+    });
+    let replay = convert_result(replay)?;
+    let header = replay.header;
+    let sim = replay.body.sim;
+
+    let apm = state
+        .actions
+        .iter()
+        .map(|(source, actions)| {
+            let ticks = sim.players_last_tick.get(source).unwrap_or(&sim.tick);
+            let minutes = *ticks as f32 / TICKS_PER_MINUTE;
+            let apm = if minutes > 0.0 {
+                *actions as f32 / minutes
+            } else {
+                0.0
+            };
+            (*source, apm)
+        })
+        .collect();
+
+    let command_counts = PyDict::new(py);
+    for (source, counts) in state.counts {
+        let dict = PyDict::new(py);
+        for (id, count) in counts {
+            dict.set_item(command_type(py).member(id, py), count)?;
+        }
+        command_counts.set_item(source, dict)?;
+    }
+
+    let players = player_info(&header.armies);
+    let teams = Teams::new(&players, &header.players);
+
+    Ok(Summary {
+        ticks: sim.tick,
+        map: Py::new(py, MapInfo::new(&header.map_file, &header.scenario))?,
+        players: players
+            .into_iter()
+            .map(|p| Py::new(py, p))
+            .collect::<PyResult<_>>()?,
+        teams: Py::new(py, teams)?,
+        desync_ticks: sim.desync_ticks,
+        apm,
+        quit_ticks: sim.players_last_tick,
+        pauses: state.pauses.pauses.len(),
+        command_counts: command_counts.into_py(py),
+    })
+}
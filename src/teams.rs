@@ -0,0 +1,94 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::player::PlayerInfo;
+use crate::{reduce, PickleState};
+
+/// The team setup of a game, decoded from the `Team` values in `header["armies"]`.
+///
+/// Team `1` is the lobby's "no team" setting, so every army on it plays for itself. Civilian
+/// armies are not part of any team.
+#[pyclass(module = "fafreplay")]
+pub struct Teams {
+    /// Army sources (the keys of `header["armies"]`) grouped by team. Real teams come first in
+    /// order of their team number, followed by the armies without a team in order of their start
+    /// spot.
+    #[pyo3(get)]
+    pub teams: Vec<Vec<u32>>,
+    /// Names of the players that were connected to the game but didn't control an army
+    #[pyo3(get)]
+    pub observers: Vec<String>,
+}
+
+pickle_state!(Teams { teams, observers });
+
+#[pymethods]
+impl Teams {
+    #[staticmethod]
+    fn _unpickle(state: &PyDict) -> PyResult<Teams> {
+        Teams::from_state(state)
+    }
+
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, Py<PyTuple>)> {
+        reduce(slf, vec![slf.borrow().state(slf.py())?])
+    }
+
+    /// Team sizes joined by `v`, largest team first, e.g. `4v4` or `1v1v1v1`
+    #[getter]
+    fn size(&self) -> String {
+        let mut sizes: Vec<usize> = self.teams.iter().map(Vec::len).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join("v")
+    }
+
+    /// Whether there are more than two armies and every army plays for itself
+    #[getter]
+    fn ffa(&self) -> bool {
+        self.teams.len() > 2 && self.teams.iter().all(|t| t.len() == 1)
+    }
+
+    fn __len__(&self) -> usize {
+        self.teams.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Teams(size={:?}, teams={:?}, observers={:?})",
+            self.size(),
+            self.teams,
+            self.observers
+        )
+    }
+}
+
+impl Teams {
+    /// `players` should be ordered by start spot as returned by `player_info`.
+    pub fn new(players: &[PlayerInfo], connected: &HashMap<String, u32>) -> Teams {
+        let mut teams: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        let mut solo = Vec::new();
+
+        for player in players.iter().filter(|p| !p.civilian) {
+            match player.team {
+                Some(team) if team > 1 => teams.entry(team).or_default().push(player.source),
+                _ => solo.push(vec![player.source]),
+            }
+        }
+
+        let mut observers: Vec<String> = connected
+            .keys()
+            .filter(|name| !players.iter().any(|p| p.name.as_ref() == Some(name)))
+            .cloned()
+            .collect();
+        observers.sort();
+
+        Teams {
+            teams: teams.into_values().chain(solo).collect(),
+            observers,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+import struct
+
 import pytest
 
 
@@ -28,3 +30,20 @@ def fafreplay() -> bytes:
 def replay_desynced() -> bytes:
     with open("tests/data/8748707-desynced.scfareplay", "rb") as f:
         return f.read()
+
+
+###########
+# Helpers #
+###########
+
+
+def lua_encode(obj) -> bytes:
+    """Encode a python value the way Lua values are stored in replays"""
+    if isinstance(obj, bool):
+        return b"\x03" + bytes([obj])
+    if isinstance(obj, (int, float)):
+        return b"\x00" + struct.pack("<f", obj)
+    if isinstance(obj, str):
+        return b"\x01" + obj.encode() + b"\x00"
+    items = b"".join(lua_encode(k) + lua_encode(v) for k, v in obj.items())
+    return b"\x04" + items + b"\x05"
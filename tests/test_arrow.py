@@ -0,0 +1,274 @@
+import ctypes
+import json
+
+import pytest
+
+from fafreplay import Parser, commands, write_parquet
+
+
+class ArrowSchema(ctypes.Structure):
+    pass
+
+
+ArrowSchema._fields_ = [
+    ("format", ctypes.c_char_p),
+    ("name", ctypes.c_char_p),
+    ("metadata", ctypes.c_char_p),
+    ("flags", ctypes.c_int64),
+    ("n_children", ctypes.c_int64),
+    ("children", ctypes.POINTER(ctypes.POINTER(ArrowSchema))),
+    ("dictionary", ctypes.POINTER(ArrowSchema)),
+    ("release", ctypes.CFUNCTYPE(None, ctypes.POINTER(ArrowSchema))),
+    ("private_data", ctypes.c_void_p),
+]
+
+
+class ArrowArray(ctypes.Structure):
+    pass
+
+
+ArrowArray._fields_ = [
+    ("length", ctypes.c_int64),
+    ("null_count", ctypes.c_int64),
+    ("offset", ctypes.c_int64),
+    ("n_buffers", ctypes.c_int64),
+    ("n_children", ctypes.c_int64),
+    ("buffers", ctypes.POINTER(ctypes.c_void_p)),
+    ("children", ctypes.POINTER(ctypes.POINTER(ArrowArray))),
+    ("dictionary", ctypes.POINTER(ArrowArray)),
+    ("release", ctypes.CFUNCTYPE(None, ctypes.POINTER(ArrowArray))),
+    ("private_data", ctypes.c_void_p),
+]
+
+
+class ArrowArrayStream(ctypes.Structure):
+    pass
+
+
+ArrowArrayStream._fields_ = [
+    (
+        "get_schema",
+        ctypes.CFUNCTYPE(
+            ctypes.c_int,
+            ctypes.POINTER(ArrowArrayStream),
+            ctypes.POINTER(ArrowSchema)
+        )
+    ),
+    (
+        "get_next",
+        ctypes.CFUNCTYPE(
+            ctypes.c_int,
+            ctypes.POINTER(ArrowArrayStream),
+            ctypes.POINTER(ArrowArray)
+        )
+    ),
+    (
+        "get_last_error",
+        ctypes.CFUNCTYPE(ctypes.c_char_p, ctypes.POINTER(ArrowArrayStream))
+    ),
+    ("release", ctypes.CFUNCTYPE(None, ctypes.POINTER(ArrowArrayStream))),
+    ("private_data", ctypes.c_void_p),
+]
+
+
+def from_capsule(capsule, name: bytes, struct):
+    get_pointer = ctypes.pythonapi.PyCapsule_GetPointer
+    get_pointer.restype = ctypes.c_void_p
+    get_pointer.argtypes = [ctypes.py_object, ctypes.c_char_p]
+    return ctypes.cast(get_pointer(capsule, name), ctypes.POINTER(struct)).contents
+
+
+def children(obj):
+    return [obj.children[i].contents for i in range(obj.n_children)]
+
+
+def uint32_values(array):
+    values = ctypes.cast(array.buffers[1], ctypes.POINTER(ctypes.c_uint32))
+    return values[:array.length]
+
+
+def string_values(array):
+    offsets = ctypes.cast(array.buffers[1], ctypes.POINTER(ctypes.c_int32))
+    data = ctypes.cast(array.buffers[2], ctypes.POINTER(ctypes.c_char))
+    return [
+        data[offsets[i]:offsets[i + 1]].decode()
+        for i in range(array.length)
+    ]
+
+
+@pytest.fixture
+def parser() -> Parser:
+    return Parser(
+        commands=[
+            commands.Advance,
+            commands.SetCommandSource,
+            commands.VerifyChecksum,
+            commands.IssueCommand,
+        ],
+        stop_on_desync=False,
+    )
+
+
+def test_parse_arrow(parser: Parser, replay_desynced: bytes):
+    pytest.importorskip("pyarrow")
+    result = parser.parse_arrow(replay_desynced)
+
+    header = result["header"].to_pyarrow().to_pylist()[0]
+    assert header["scfa_version"] == "Supreme Commander v1.50.3700"
+    assert header["army_count"] == 8
+    assert len(header["players"]) == 8
+    assert header["map_info"]["map_file"] == header["map_file"]
+    assert len(header["player_info"]) == 8
+    assert sum(len(team) for team in header["teams"]["teams"]) == 8
+    assert header["game_options"]["share"] == "ShareUntilDeath"
+    assert json.loads(header["scenario"])["Options"]["Share"] == "ShareUntilDeath"
+    assert json.loads(header["armies"])["2"]["PlayerName"] == "Gan9sta"
+
+    batches = {
+        name: batch.to_pyarrow()
+        for name, batch in result["commands"].items()
+    }
+    assert set(batches) == {
+        "Advance",
+        "SetCommandSource",
+        "VerifyChecksum",
+        "IssueCommand",
+    }
+    assert batches["Advance"].num_rows == 27868
+    assert batches["VerifyChecksum"].num_rows == 4320
+
+    issue = batches["IssueCommand"].to_pylist()[0]
+    assert issue["tick"] == 33
+    assert issue["source"] == 4
+    assert issue["entity_ids"] == [4194304]
+    assert issue["blueprint"] == "xsb0101"
+    assert issue["target"] == {"id": None, "x": 269.5, "y": 40.953125, "z": 459.5}
+    assert issue["formation"] is None
+    assert json.loads(issue["upgrades"]) is None
+
+
+def test_parse_arrow_pycapsule(parser: Parser, replay_desynced: bytes):
+    pa = pytest.importorskip("pyarrow")
+    if not hasattr(pa.RecordBatch, "_import_from_c_capsule"):
+        pytest.skip("pyarrow does not support the PyCapsule interface")
+    batch = parser.parse_arrow(replay_desynced)["commands"]["IssueCommand"]
+
+    assert pa.record_batch(batch).equals(batch.to_pyarrow())
+    assert pa.table(batch).num_rows == 11276
+
+
+def test_arrow_c_schema(parser: Parser, replay_desynced: bytes):
+    batch = parser.parse_arrow(replay_desynced)["commands"]["IssueCommand"]
+    capsule = batch.__arrow_c_schema__()
+    schema = from_capsule(capsule, b"arrow_schema", ArrowSchema)
+    fields = {child.name.decode(): child for child in children(schema)}
+
+    assert schema.format == b"+s"
+    assert list(fields) == batch.column_names
+    assert batch.column_names[:4] == ["tick", "source", "entity_ids", "id"]
+    assert fields["tick"].format == b"I"
+    assert fields["source"].format == b"C"
+    assert fields["entity_ids"].format == b"+l"
+    assert children(fields["entity_ids"])[0].format == b"I"
+    assert fields["blueprint"].format == b"u"
+    assert fields["upgrades"].format == b"u"
+    assert fields["clear_queue"].format == b"b"
+    assert [child.name for child in children(fields["target"])] == [
+        b"id", b"x", b"y", b"z"
+    ]
+
+
+def test_arrow_c_array(parser: Parser, replay_desynced: bytes):
+    batch = parser.parse_arrow(replay_desynced)["commands"]["IssueCommand"]
+    # The capsules own the exported structs and must be kept alive
+    capsules = batch.__arrow_c_array__()
+    array = from_capsule(capsules[1], b"arrow_array", ArrowArray)
+    columns = dict(zip(batch.column_names, children(array)))
+
+    assert array.length == batch.num_rows == 11276
+    assert uint32_values(columns["tick"])[0] == 33
+    assert columns["tick"].null_count == 0
+    assert string_values(columns["blueprint"])[0] == "xsb0101"
+    # Position targets have a null entity id
+    assert columns["target"].null_count < array.length
+    assert children(columns["target"])[0].null_count > 0
+
+    # Releasing the array early must not free it a second time with the capsule
+    array.release(ctypes.pointer(array))
+    assert not array.release
+
+
+def test_arrow_c_array_lua(replay_desynced: bytes):
+    parser = Parser(commands=[commands.LuaSimCallback], stop_on_desync=False)
+    batch = parser.parse_arrow(replay_desynced)["commands"]["LuaSimCallback"]
+    capsules = batch.__arrow_c_array__()
+    array = from_capsule(capsules[1], b"arrow_array", ArrowArray)
+    columns = dict(zip(batch.column_names, children(array)))
+
+    args = [json.loads(value) for value in string_values(columns["args"])]
+    assert len(args) == batch.num_rows
+    assert any(isinstance(value, dict) and value for value in args)
+
+
+def test_arrow_c_stream(parser: Parser, replay_desynced: bytes):
+    batch = parser.parse_arrow(replay_desynced)["commands"]["Advance"]
+    capsule = batch.__arrow_c_stream__()
+    stream = from_capsule(capsule, b"arrow_array_stream", ArrowArrayStream)
+
+    schema = ArrowSchema()
+    assert stream.get_schema(ctypes.pointer(stream), ctypes.pointer(schema)) == 0
+    assert [child.name for child in children(schema)] == [
+        b"tick", b"source", b"ticks"
+    ]
+    schema.release(ctypes.pointer(schema))
+
+    array = ArrowArray()
+    assert stream.get_next(ctypes.pointer(stream), ctypes.pointer(array)) == 0
+    assert array.length == 27868
+    array.release(ctypes.pointer(array))
+
+    end = ArrowArray()
+    assert stream.get_next(ctypes.pointer(stream), ctypes.pointer(end)) == 0
+    assert not end.release
+
+
+def test_parse_arrow_header(parser: Parser, replay_desynced: bytes):
+    header = parser.parse_arrow(replay_desynced)["header"]
+
+    assert header.num_rows == 1
+    assert header.column_names == [
+        "scfa_version",
+        "replay_version",
+        "map_info",
+        "map_file",
+        "mod_info",
+        "mods",
+        "game_options",
+        "scenario",
+        "players",
+        "cheats_enabled",
+        "army_count",
+        "teams",
+        "player_info",
+        "armies",
+        "seed",
+    ]
+
+
+def test_parse_arrow_tick_and_source(replay_desynced: bytes):
+    pytest.importorskip("pyarrow")
+    parser = Parser(commands=[commands.IssueCommand], stop_on_desync=False)
+    batches = parser.parse_arrow(replay_desynced)["commands"]
+
+    assert set(batches) == {"IssueCommand"}
+    issue = batches["IssueCommand"].to_pylist()[0]
+    assert issue["tick"] == 33
+    assert issue["source"] == 4
+
+
+def test_write_parquet(parser: Parser, replay_desynced: bytes, tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    write_parquet(parser, replay_desynced, tmp_path)
+
+    assert pq.read_table(tmp_path / "header.parquet").num_rows == 1
+    assert pq.read_table(tmp_path / "IssueCommand.parquet").num_rows == 11276
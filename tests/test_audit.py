@@ -0,0 +1,55 @@
+import struct
+
+from fafreplay import CommandType, audit_report, body_offset
+
+
+def command(type_: int, payload: bytes) -> bytes:
+    return bytes([type_]) + struct.pack("<H", len(payload) + 3) + payload
+
+
+def test_audit_report_clean(replay_desynced: bytes):
+    report = audit_report(replay_desynced)
+
+    assert report.cheats_enabled is False
+    assert report.commands == []
+    assert report.flagged is False
+
+
+def test_audit_report(replay_desynced: bytes):
+    commands = (
+        command(CommandType.Advance, struct.pack("<I", 5)) +
+        command(CommandType.SetCommandSource, b"\x03") +
+        command(CommandType.ExecuteLuaInSim, b"LOG('hello')\x00") +
+        command(CommandType.DestroyEntity, struct.pack("<I", 42)) +
+        command(CommandType.WarpEntity, struct.pack("<Ifff", 42, 1, 2, 3))
+    )
+    offset = body_offset(replay_desynced)
+    data = replay_desynced[:offset] + commands + replay_desynced[offset:]
+
+    report = audit_report(data)
+
+    assert report.cheats_enabled is False
+    assert report.flagged is True
+    assert report.commands == [
+        {
+            "name": CommandType.ExecuteLuaInSim,
+            "code": "LOG('hello')",
+            "tick": 5,
+            "source": 3,
+        },
+        {
+            "name": CommandType.DestroyEntity,
+            "unit": 42,
+            "tick": 5,
+            "source": 3,
+        },
+        {
+            "name": CommandType.WarpEntity,
+            "unit": 42,
+            "x": 1.0,
+            "y": 2.0,
+            "z": 3.0,
+            "tick": 5,
+            "source": 3,
+        },
+    ]
@@ -0,0 +1,63 @@
+from fafreplay import blueprints
+
+
+def test_decode_structure():
+    info = blueprints.decode("ueb0101")
+
+    assert info.id == "ueb0101"
+    assert info.faction == "UEF"
+    assert info.category == "structure"
+    assert info.tier == 1
+    assert info.expansion == "Supreme Commander"
+    assert info.name == "T1 Land Factory"
+
+
+def test_decode_unit():
+    info = blueprints.decode("xsl0305")
+
+    assert info.faction == "Seraphim"
+    assert info.category == "land"
+    assert info.tier == 3
+    assert info.expansion == "Forged Alliance"
+    assert info.name == "Usha-Ah"
+
+
+def test_decode_experimental():
+    assert blueprints.decode("url0402").tier == 4
+    assert blueprints.decode("xab1401").tier == 4
+
+
+def test_decode_normalizes_id():
+    assert blueprints.decode("UAL0105").id == "ual0105"
+    assert blueprints.decode("/units/uel0001/uel0001_unit.bp").id == "uel0001"
+
+
+def test_decode_command_unit():
+    info = blueprints.decode("url0001")
+
+    assert info.tier is None
+    assert info.name == "Armored Command Unit"
+
+
+def test_decode_unknown_name():
+    info = blueprints.decode("uel9999")
+
+    assert info.faction == "UEF"
+    assert info.name is None
+
+
+def test_decode_invalid():
+    assert blueprints.decode("") is None
+    assert blueprints.decode("foobar1") is None
+    assert blueprints.decode("uzl0101") is None
+
+
+def test_display_name():
+    assert blueprints.display_name("xsb1103") == "T1 Mass Extractor"
+    assert blueprints.display_name("UEL0401") == "Fatboy"
+    assert blueprints.display_name("xeb1103") is None
+
+
+def test_names():
+    assert blueprints.NAMES["ura0401"] == "Soul Ripper"
+    assert blueprints.NAMES["urb0101"] == "T1 Land Factory"
@@ -0,0 +1,280 @@
+import struct
+from collections import Counter
+
+import pytest
+
+from fafreplay import body_offset, callbacks, pings, transfers
+from fafreplay.callbacks import PingEvent, Transfer
+
+from conftest import lua_encode
+
+
+def lua_sim_callback(func: str, args, selection=()) -> bytes:
+    payload = (
+        func.encode() + b"\x00" + lua_encode(args) +
+        struct.pack("<I", len(selection)) +
+        b"".join(struct.pack("<I", unit) for unit in selection)
+    )
+    return b"\x16" + struct.pack("<H", len(payload) + 3) + payload
+
+
+@pytest.fixture
+def restore_decoders():
+    decoders = dict(callbacks.DECODERS)
+    yield
+    callbacks.DECODERS.clear()
+    callbacks.DECODERS.update(decoders)
+
+
+def test_iter_callbacks(replay_desynced: bytes):
+    events = list(callbacks.iter_callbacks(replay_desynced))
+
+    counts = Counter(event.func for event in events)
+    assert counts["GiveUnitsToPlayer"] == 7
+    assert counts["SpawnPing"] == 76
+
+    ticks = [event.tick for event in events]
+    assert ticks == sorted(ticks)
+    assert all(0 <= event.source < 16 for event in events)
+
+
+def test_decode_unit_transfer(replay_desynced: bytes):
+    event = next(
+        event for event in callbacks.iter_callbacks(replay_desynced)
+        if event.func == "GiveUnitsToPlayer"
+    )
+
+    assert event.value == callbacks.UnitTransfer(
+        from_army=7,
+        to_army=3,
+        units=event.selection
+    )
+
+
+def test_decode_resources_and_chat(replay_desynced: bytes):
+    values = [
+        event.value for event in callbacks.iter_callbacks(replay_desynced)
+        if event.func == "GiveResourcesToPlayer"
+    ]
+
+    chat = [v for v in values if isinstance(v, callbacks.ChatMessage)]
+    transfers = [v for v in values if isinstance(v, callbacks.ResourceTransfer)]
+    assert len(chat) + len(transfers) == len(values) == 801
+    assert chat and transfers
+    assert all(isinstance(c.text, str) for c in chat)
+    assert all(0 <= t.mass <= 1 and 0 <= t.energy <= 1 for t in transfers)
+
+
+def test_decode_ping():
+    ping = callbacks.decode({
+        "func": "SpawnPing",
+        "args": {
+            "Owner": 4.0,
+            "Type": b"alert",
+            "Lifetime": 6.0,
+            "Location": {1.0: 10.0, 2.0: 20.0, 3.0: 30.0},
+        },
+        "selection": [],
+    })
+
+    assert ping == callbacks.Ping(
+        owner=4,
+        type="alert",
+        position=(10.0, 20.0, 30.0),
+        lifetime=6.0,
+        name=None
+    )
+
+
+def test_decode_unit_order():
+    order = callbacks.decode({
+        "func": "CapMex",
+        "args": {"target": b"2097156"},
+        "selection": [1, 2],
+    })
+
+    assert order == callbacks.UnitOrder(
+        order="CapMex",
+        units=[1, 2],
+        target=2097156
+    )
+
+
+def test_decode_teamkill_report(replay_desynced: bytes):
+    offset = body_offset(replay_desynced)
+    data = (
+        replay_desynced[:offset] +
+        b"\x00" + struct.pack("<HI", 7, 5) +  # Advance
+        b"\x01" + struct.pack("<HB", 4, 3) +  # SetCommandSource
+        lua_sim_callback("TeamkillReport", {
+            "victim": 2.0,
+            "instigator": 5.0,
+            "gametime": 123.5,
+        }) +
+        replay_desynced[offset:]
+    )
+    event = next(callbacks.iter_callbacks(data))
+
+    assert event.tick == 5
+    assert event.source == 3
+    assert event.value == callbacks.TeamkillReport(
+        victim=2,
+        instigator=5,
+        game_time=123.5
+    )
+
+
+def test_decode_unknown():
+    assert callbacks.decode({
+        "func": "NotARealCallback",
+        "args": {},
+        "selection": [],
+    }) is None
+
+
+def test_decode_args_not_a_table(replay_desynced: bytes):
+    assert callbacks.decode({
+        "func": "SpawnPing",
+        "args": b"alert",
+        "selection": [],
+    }) is None
+
+    offset = body_offset(replay_desynced)
+    data = (
+        replay_desynced[:offset] +
+        lua_sim_callback("GiveUnitsToPlayer", "7", [1, 2]) +
+        replay_desynced[offset:]
+    )
+    event = next(callbacks.iter_callbacks(data))
+    assert event.func == "GiveUnitsToPlayer"
+    assert event.args == b"7"
+    assert event.value is None
+
+
+def test_register(restore_decoders):
+    @callbacks.register("MyModCallback")
+    def decode_my_mod(args, selection):
+        return args["Value"] * 2
+
+    command = {"func": "MyModCallback", "args": {"Value": 2.0}, "selection": []}
+    assert callbacks.decode(command) == 4.0
+
+    callbacks.unregister("MyModCallback")
+    assert callbacks.decode(command) is None
+
+    callbacks.register("OnControlGroupApply", lambda args, selection: "group")
+    assert callbacks.decode({
+        "func": "OnControlGroupApply",
+        "args": {},
+        "selection": [],
+    }) == "group"
+
+
+def test_transfers(replay_desynced: bytes):
+    result = transfers(replay_desynced)
+
+    assert len(result) == 12
+    ticks = [t.tick for t in result]
+    assert ticks == sorted(ticks)
+
+    units = [t for t in result if t.units]
+    assert [t.unit_count for t in units] == [1, 2, 1, 86, 86, 86, 410]
+    assert units[0] == Transfer(
+        tick=4394,
+        source=6,
+        giver=7,
+        receiver=3,
+        mass=0.0,
+        energy=0.0,
+        units=[6291477]
+    )
+
+    resources = [t for t in result if not t.units]
+    assert resources[0] == Transfer(
+        tick=13819,
+        source=4,
+        giver=5,
+        receiver=1,
+        mass=1.0,
+        energy=1.0,
+        units=[]
+    )
+    assert all(t.mass or t.energy for t in resources)
+
+
+def test_pings(replay_desynced: bytes):
+    result = pings(replay_desynced)
+
+    assert len(result) == 76
+    assert result[0] == PingEvent(
+        tick=1572,
+        source=4,
+        owner=4,
+        type="alert",
+        action="ping",
+        position=(425.7808837890625, 36.94305419921875, 336.1393127441406)
+    )
+    assert {p.type for p in result} == {"alert", "move", "attack", "marker"}
+
+    markers = [p for p in result if p.type == "marker"]
+    assert len(markers) == 7
+    assert all(p.action == "create" for p in markers)
+    assert markers[0].tick == 5210
+    assert markers[0].name == "help"
+
+
+def test_pings_special(replay: bytes):
+    counts = Counter(event.func for event in callbacks.iter_callbacks(replay))
+    assert counts["SpawnSpecialPing"] == 60
+
+    spawned = [p for p in pings(replay) if p.action in ("ping", "create")]
+    assert len(spawned) == counts["SpawnPing"] + counts["SpawnSpecialPing"]
+
+
+def test_pings_marker_update(replay_desynced: bytes):
+    offset = body_offset(replay_desynced)
+    commands = (
+        lua_sim_callback("UpdateMarker", {
+            "Action": "delete",
+            "ID": 3,
+            "Owner": 2,
+        }) +
+        lua_sim_callback("UpdateMarker", {
+            "Action": "move",
+            "ID": 1,
+            "Owner": 2,
+            "Location": {1: 10.0, 2: 5.0, 3: 20.0},
+        }) +
+        lua_sim_callback("UpdateMarker", {"Action": "renew"})
+    )
+    data = replay_desynced[:offset] + commands + replay_desynced[offset:]
+
+    renew = callbacks.decode({
+        "func": "UpdateMarker",
+        "args": {"Action": b"renew"},
+        "selection": [],
+    })
+    assert renew.owner is None and renew.id is None
+
+    # The renew update is skipped
+    updates = [p for p in pings(data) if p.tick == 0]
+    assert updates == [
+        PingEvent(
+            tick=0,
+            source=0,
+            owner=2,
+            type="marker",
+            action="delete",
+            position=None,
+            marker_id=3
+        ),
+        PingEvent(
+            tick=0,
+            source=0,
+            owner=2,
+            type="marker",
+            action="move",
+            position=(10.0, 5.0, 20.0),
+            marker_id=1
+        ),
+    ]
@@ -1,4 +1,11 @@
-from fafreplay import commands
+from fafreplay import (
+    CommandType,
+    LuaType,
+    TargetType,
+    UnitCommandType,
+    commands,
+    unit_commands
+)
 
 
 def test_command_names():
@@ -28,3 +35,40 @@ def test_command_names():
     assert commands.NAMES[commands.EndGame] == "EndGame"
 
     assert len(commands.NAMES) == commands.MAX + 1
+
+
+def test_unit_command_names():
+    assert unit_commands.NAMES[unit_commands.Stop] == "Stop"
+    assert unit_commands.NAMES[unit_commands.Move] == "Move"
+    assert unit_commands.NAMES[unit_commands.BuildMobile] == "BuildMobile"
+    assert unit_commands.NAMES[unit_commands.Attack] == "Attack"
+    assert unit_commands.NAMES[unit_commands.Guard] == "Guard"
+    assert unit_commands.NAMES[unit_commands.Patrol] == "Patrol"
+    assert unit_commands.NAMES[unit_commands.Ferry] == "Ferry"
+    assert unit_commands.NAMES[unit_commands.Reclaim] == "Reclaim"
+    assert unit_commands.NAMES[unit_commands.Upgrade] == "Upgrade"
+    assert unit_commands.NAMES[unit_commands.Dock] == "Dock"
+
+    assert len(unit_commands.NAMES) == unit_commands.MAX + 1
+
+
+def test_unit_command_type():
+    assert UnitCommandType.Move == unit_commands.Move
+    assert UnitCommandType(unit_commands.Reclaim).name == "Reclaim"
+    assert [t.name for t in UnitCommandType] == unit_commands.NAMES
+
+
+def test_command_type():
+    assert CommandType.Advance == commands.Advance
+    assert CommandType.EndGame == commands.EndGame
+    assert [t.name for t in CommandType] == commands.NAMES
+
+
+def test_target_type():
+    assert [t.name for t in TargetType] == ["None", "Entity", "Position"]
+
+
+def test_lua_type():
+    assert LuaType.Float == 0
+    assert LuaType.Table == 4
+    assert LuaType.TableEnd == 5
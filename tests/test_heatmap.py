@@ -0,0 +1,72 @@
+import pytest
+
+from fafreplay import CommandType, Parser, command_heatmap, commands
+
+
+def reference_heatmap(data: bytes, rows: int, cols: int, size: int = 512):
+    parser = Parser(
+        commands=[
+            commands.SetCommandSource,
+            commands.IssueCommand,
+            commands.SetCommandTarget,
+            commands.SetCommandCells,
+        ],
+        save_commands=True,
+        stop_on_desync=False
+    )
+    counts = {}
+    source = 0
+    for command in parser.parse(data)["body"]["commands"]:
+        if command["name"] == CommandType.SetCommandSource:
+            source = command["id"]
+            continue
+
+        if command["name"] == CommandType.SetCommandCells:
+            position = command["position"]
+        else:
+            position = command["target"]
+        if not position or "x" not in position:
+            continue
+
+        row = min(max(int(position["z"] / size * rows), 0), rows - 1)
+        col = min(max(int(position["x"] / size * cols), 0), cols - 1)
+        key = (source, row, col)
+        counts[key] = counts.get(key, 0) + 1
+    return counts
+
+
+def test_command_heatmap(replay_desynced: bytes):
+    pytest.importorskip("numpy")
+
+    heatmap = command_heatmap(replay_desynced)
+
+    assert heatmap.shape == (8, 64, 64)
+    assert int(heatmap.sum()) == 7373
+    assert int(heatmap[2].sum()) == 1518
+
+
+def test_command_heatmap_source(replay_desynced: bytes):
+    pytest.importorskip("numpy")
+
+    heatmap = command_heatmap(replay_desynced, grid=(4, 8), source=2)
+
+    assert heatmap.shape == (4, 8)
+    expected = reference_heatmap(replay_desynced, 4, 8)
+    assert heatmap.tolist() == [
+        [expected.get((2, row, col), 0) for col in range(8)]
+        for row in range(4)
+    ]
+
+
+def test_command_heatmap_unknown_source(replay_desynced: bytes):
+    pytest.importorskip("numpy")
+
+    heatmap = command_heatmap(replay_desynced, grid=(2, 2), source=40)
+
+    assert heatmap.shape == (2, 2)
+    assert int(heatmap.sum()) == 0
+
+
+def test_command_heatmap_invalid_grid(replay_desynced: bytes):
+    with pytest.raises(ValueError):
+        command_heatmap(replay_desynced, grid=(0, 64))
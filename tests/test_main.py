@@ -0,0 +1,53 @@
+import json
+
+from fafreplay.__main__ import main
+
+REPLAY = "tests/data/8748707-desynced.scfareplay"
+
+
+def test_info(capsys):
+    assert main(["info", REPLAY]) == 0
+
+    out = capsys.readouterr().out
+    assert "Map: /maps/canis 4v4 spezial edition.v0002" in out
+    assert "Duration: 0:46:26.800000" in out
+    assert "Pholiavion (quit at 0:34:29.100000)" in out
+    assert "Desynced: 268 times, first at 0:15:10.500000" in out
+
+
+def test_ticks(capsys):
+    assert main(["ticks", REPLAY]) == 0
+
+    assert capsys.readouterr().out == "27868\n"
+
+
+def test_commands(capsys):
+    assert main([
+        "commands", REPLAY,
+        "--name", "IssueCommand",
+        "--source", "4",
+        "--limit", "5000"
+    ]) == 0
+
+    lines = capsys.readouterr().out.splitlines()
+    first = json.loads(lines[0])
+    assert first["name"] == "IssueCommand"
+    assert first["tick"] == 33
+    assert first["source"] == 4
+    assert first["blueprint"] == "xsb0101"
+    assert all(json.loads(line)["source"] == 4 for line in lines)
+
+
+def test_invalid_replay(capsys):
+    assert main(["ticks", "tests/data/1418712.scfareplay"]) == 2
+
+    assert "Error" in capsys.readouterr().err
+
+
+def test_extract_scfareplay(capsys, tmp_path):
+    output = str(tmp_path / "out.scfareplay")
+    assert main(["extract", REPLAY, "-o", output]) == 2
+
+    err = capsys.readouterr().err
+    assert "not a valid .fafreplay file" in err
+    assert "Traceback" not in err
@@ -3,8 +3,42 @@ import fafreplay
 
 def test_available_objects():
     assert fafreplay.Parser
+    assert fafreplay.PlayerInfo
+    assert fafreplay.GameOptions
+    assert fafreplay.MapInfo
+    assert fafreplay.ModInfo
+    assert fafreplay.Teams
+    assert fafreplay.CommandQueueTracker
+    assert fafreplay.QueuedCommand
+    assert fafreplay.ProductionEvent
+    assert fafreplay.Pause
+    assert fafreplay.pause_timeline
+    assert fafreplay.AuditReport
+    assert fafreplay.audit_report
+    assert fafreplay.uses_sim_mod
     assert fafreplay.ReplayReadError
     assert fafreplay.ReplayDesyncedError
     assert fafreplay.body_offset
     assert fafreplay.body_ticks
     assert fafreplay.extract_scfa
+    assert fafreplay.dump_json
+    assert fafreplay.write_parquet
+    assert fafreplay.blueprints
+    assert fafreplay.unit_commands
+    assert fafreplay.UnitCommandType
+    assert fafreplay.CommandType
+    assert fafreplay.TargetType
+    assert fafreplay.LuaType
+    assert fafreplay.callbacks
+    assert fafreplay.callbacks.register
+    assert fafreplay.callbacks.decode
+    assert fafreplay.callbacks.iter_callbacks
+    assert fafreplay.transfers
+    assert fafreplay.pings
+    assert fafreplay.command_heatmap
+    assert fafreplay.render
+    assert fafreplay.Summary
+    assert fafreplay.summarize
+    assert fafreplay.body_info
+    assert fafreplay.header_info
+    assert fafreplay.LuaTable
@@ -1,9 +1,25 @@
+import copy
+import json
+import pickle
+import struct
+from collections.abc import Mapping
 from time import perf_counter
 from typing import List
 
 import pytest
 
-from fafreplay import Parser, ReplayReadError, commands
+from fafreplay import (
+    LuaTable,
+    Parser,
+    ReplayReadError,
+    commands,
+    dump_json,
+    CommandType,
+    UnitCommandType,
+    uses_sim_mod
+)
+
+from conftest import lua_encode
 
 
 @pytest.fixture
@@ -32,6 +48,18 @@ def parser_full() -> Parser:
     )
 
 
+def attributes(obj) -> dict:
+    return {
+        name: getattr(obj, name)
+        for name in dir(obj) if not name.startswith("_")
+    }
+
+
+def copies(obj) -> list:
+    """A pickled and a deep copied copy of `obj`"""
+    return [pickle.loads(pickle.dumps(obj)), copy.deepcopy(obj)]
+
+
 def test_constructor_list():
     Parser(
         limit=10,
@@ -140,6 +168,173 @@ def test_parse_header(parser: Parser, replay: bytes):
     assert result["replay_version"] == "Replay v1.9"
 
 
+def test_parse_header_player_info(parser: Parser, replay_desynced: bytes):
+    players = parser.parse_header(replay_desynced)["player_info"]
+
+    assert [p.start_spot for p in players] == [1, 2, 3, 4, 5, 6, 7, 8]
+
+    player = players[2]
+    assert player.source == 2
+    assert player.name == "Gan9sta"
+    assert player.army_name == "ARMY_3"
+    assert player.faction == 1
+    assert player.faction_name == "UEF"
+    assert player.team == 3
+    assert player.color == 5
+    assert player.ai is False
+    assert player.civilian is False
+    assert player.rating_mean == pytest.approx(1336.87)
+    assert player.rating_deviation == pytest.approx(133.955)
+    assert player.clan is None
+    assert player.country == "ua"
+
+    assert players[7].clan == "GB"
+
+    for restored in copies(players):
+        assert list(map(attributes, restored)) == list(map(attributes, players))
+
+
+def test_parse_header_game_options(parser: Parser, replay_desynced: bytes):
+    options = parser.parse_header(replay_desynced)["game_options"]
+
+    assert options.victory == "demoralization"
+    assert options.share == "ShareUntilDeath"
+    assert options.unit_cap == 1000
+    assert options.cheats_enabled is False
+    assert options.cheat_mult == 1.0
+    assert options.build_mult == 1.0
+    assert options.timeouts == 3
+    assert options.game_speed == "normal"
+    assert options.fog_of_war == "explored"
+    assert options.prebuilt_units is False
+    assert options.no_rush == 0
+    assert options.share_unit_cap == "none"
+    assert options.team_lock is True
+
+    assert options.extra["Title"] == b"7/8 all welcome"
+    assert "Victory" not in options.extra
+
+    for restored in copies(options):
+        assert attributes(restored) == attributes(options)
+
+
+def test_parse_header_map_info(parser: Parser, replay_desynced: bytes):
+    map_info = parser.parse_header(replay_desynced)["map_info"]
+
+    assert map_info.map_file == (
+        "/maps/canis 4v4 spezial edition.v0002/canis 4v4 spezial edition.scmap"
+    )
+    assert map_info.folder == "canis 4v4 spezial edition.v0002"
+    assert map_info.name == "canis 4v4 spezial edition"
+    assert map_info.version == 2
+    assert map_info.size == (512, 512)
+    assert map_info.playable_area is None
+    assert map_info.start_positions is None
+
+    for restored in copies(map_info):
+        assert attributes(restored) == attributes(map_info)
+
+
+def test_parse_header_teams(parser: Parser, replay_desynced: bytes):
+    teams = parser.parse_header(replay_desynced)["teams"]
+
+    assert teams.teams == [[1, 3, 5, 7], [0, 2, 4, 6]]
+    assert teams.observers == []
+    assert teams.size == "4v4"
+    assert teams.ffa is False
+    assert len(teams) == 2
+
+    for restored in copies(teams):
+        assert attributes(restored) == attributes(teams)
+
+
+def test_parse_unit_command_type(replay_desynced: bytes):
+    parser = Parser(
+        commands=[commands.IssueCommand],
+        save_commands=True,
+        stop_on_desync=False
+    )
+    result = parser.parse(replay_desynced)
+
+    types = [command["type"] for command in result["body"]["commands"]]
+    assert all(isinstance(type_, UnitCommandType) for type_ in types)
+    assert types.count(UnitCommandType.Move) == 5087
+    assert types.count(UnitCommandType.BuildMobile) == 1345
+
+
+def test_parse_command_type(replay_desynced: bytes):
+    parser = Parser(
+        commands=[CommandType.Advance, CommandType.EndGame],
+        save_commands=True,
+        stop_on_desync=False
+    )
+    result = parser.parse(replay_desynced)
+
+    names = [command["name"] for command in result["body"]["commands"]]
+    assert all(isinstance(name, CommandType) for name in names)
+    assert names.count(CommandType.Advance) == 27868
+    assert names[-1] == CommandType.EndGame
+    assert names[-1].name == "EndGame"
+
+
+def with_mods(data: bytes, mods: dict) -> bytes:
+    """Replace the empty mods table of a replay"""
+    empty = struct.pack("<I", 2) + b"\x04\x05"
+    offset = data.index(b".scmap\x00\r\n\x1a\x00") + 11
+    assert data[offset:offset + len(empty)] == empty
+
+    encoded = lua_encode(mods)
+    return (
+        data[:offset] +
+        struct.pack("<I", len(encoded)) + encoded +
+        data[offset + len(empty):]
+    )
+
+
+def test_parse_header_mod_info(parser: Parser, replay_desynced: bytes):
+    data = with_mods(replay_desynced, {
+        1: {
+            "uid": "9e8ea941-c306-4751-b367-a11000000502",
+            "name": "BlackOps Unleashed",
+            "version": 5,
+            "author": "Lt_hawkeye",
+            "location": "/mods/blackops",
+            "ui_only": False
+        },
+        2: {
+            "uid": "0faf3333-1122-633s-ya-VX0000001000",
+            "name": "Supreme Economy",
+            "version": 0.5,
+            "ui_only": True
+        }
+    })
+    header = parser.parse_header(data)
+
+    sim_mod, ui_mod = header["mod_info"]
+    assert sim_mod.uid == "9e8ea941-c306-4751-b367-a11000000502"
+    assert sim_mod.name == "BlackOps Unleashed"
+    assert sim_mod.version == 5
+    assert sim_mod.author == "Lt_hawkeye"
+    assert sim_mod.location == "/mods/blackops"
+    assert sim_mod.ui_only is False
+    assert ui_mod.version == 0.5
+    assert ui_mod.author is None
+    assert ui_mod.ui_only is True
+
+    for restored in copies(header["mod_info"]):
+        assert list(map(attributes, restored)) == [
+            attributes(sim_mod),
+            attributes(ui_mod)
+        ]
+
+    assert uses_sim_mod(header, "9E8EA941-C306-4751-B367-A11000000502")
+    assert not uses_sim_mod(header, "0faf3333-1122-633s-ya-VX0000001000")
+    assert not uses_sim_mod(
+        parser.parse_header(replay_desynced),
+        "9e8ea941-c306-4751-b367-a11000000502"
+    )
+
+
 def test_parse_desynced(parser: Parser, replay_desynced: bytes):
     with pytest.raises(ReplayReadError):
         parser.parse(replay_desynced)
@@ -152,6 +347,117 @@ def test_parse_full_desynced(parser_full: Parser, replay_desynced: bytes):
     assert len(result["body"]["sim"]["desync_ticks"]) == 268
 
 
+def test_parse_columns(replay_desynced: bytes):
+    pytest.importorskip("numpy")
+    parser = Parser(
+        commands=[
+            commands.Advance,
+            commands.SetCommandSource,
+            commands.IssueCommand,
+        ],
+        stop_on_desync=False,
+    )
+    result = parser.parse_columns(replay_desynced)
+    columns = result["commands"]
+
+    assert result["sim"]["tick"] == 27868
+    assert len(columns) == 254908
+    assert (columns["command"] == commands.IssueCommand).sum() == 11276
+
+    first = columns[columns["command"] == commands.IssueCommand][0]
+    assert first["tick"] == 33
+    assert first["source"] == 4
+    assert first["x"] == 269.5
+    assert first["target"] == -1
+    assert result["blueprints"][first["blueprint"]] == "xsb0101"
+
+
+def test_parse_columns_tick_and_source(replay_desynced: bytes):
+    pytest.importorskip("numpy")
+    parser = Parser(commands=[commands.IssueCommand], stop_on_desync=False)
+    result = parser.parse_columns(replay_desynced)
+    columns = result["commands"]
+
+    assert result["sim"]["tick"] == 27868
+    assert len(columns) == 11276
+    assert columns[0]["tick"] == 33
+    assert columns[0]["source"] == 4
+
+
+def test_parse_columns_limit(replay_desynced: bytes):
+    pytest.importorskip("numpy")
+    parser = Parser(commands=[commands.IssueCommand], limit=10, stop_on_desync=False)
+    columns = parser.parse_columns(replay_desynced)["commands"]
+
+    assert len(columns) == 10
+    assert columns[0]["tick"] == 33
+
+
+def test_parse_commands(replay_desynced: bytes):
+    parser = Parser(commands=[commands.IssueCommand], stop_on_desync=False)
+    rows = parser.parse_commands(replay_desynced)
+
+    assert len(rows) == 11276
+    tick, source, command = rows[0]
+    assert tick == 33
+    assert source == 4
+    assert command["name"] == CommandType.IssueCommand
+    assert command["blueprint"] == "xsb0101"
+
+
+def test_parse_commands_json(replay_desynced: bytes):
+    parser = Parser(commands=[commands.IssueCommand], limit=10, stop_on_desync=False)
+    rows = parser.parse_commands(replay_desynced, json=True)
+
+    assert len(rows) == 10
+    tick, source, command = rows[0]
+    assert (tick, source) == (33, 4)
+    assert json.loads(command)["name"] == "IssueCommand"
+
+
+def test_parse_json(parser_full: Parser, replay_desynced: bytes):
+    result = json.loads(parser_full.parse_json(replay_desynced))
+
+    header = result["header"]
+    assert header["map_file"].startswith("/maps/canis 4v4 spezial edition")
+    assert header["scenario"]["size"] == [512.0, 512.0]
+    assert header["scenario"]["Options"]["Share"] == "ShareUntilDeath"
+    assert header["armies"]["2"]["PlayerName"] == "Gan9sta"
+
+    sim = result["body"]["sim"]
+    assert sim["desync_tick"] == 9105
+    assert sim["checksum"] == "9f3b6b15723ac0b57a089e5c88bb2868"
+    assert sim["players_last_tick"]["1"] == 20691
+
+
+def test_dump_json(replay_desynced: bytes, tmp_path):
+    path = tmp_path / "replay.json"
+    dump_json(replay_desynced, path)
+
+    with open(path, encoding="utf-8") as f:
+        result = json.load(f)
+
+    commands = result["body"]["commands"]
+    assert len(commands) == 265303
+    assert commands[3] == {
+        "name": "ProcessInfoPair",
+        "unit": 0,
+        "arg1": "CustomName",
+        "arg2": "dragonite",
+    }
+
+
+def test_dump_json_utf8(tmp_path):
+    class FakeParser:
+        def parse_json(self, data):
+            return '{"name": "Ärger 中文"}'
+
+    path = tmp_path / "replay.json"
+    dump_json(b"", path, parser=FakeParser())
+
+    assert path.read_bytes().decode("utf-8") == '{"name": "Ärger 中文"}'
+
+
 def test_parse_corrupt(parser: Parser, replays_invalid: List[bytes]):
     for data in replays_invalid:
         with pytest.raises(ReplayReadError):
@@ -174,3 +480,71 @@ def test_parse_performance(parser: Parser, replay: bytes):
     elapsed = end - start
 
     assert elapsed < 0.12, "Make sure to compile in release mode"
+
+
+def test_parse_header_lua_tables(parser: Parser, replay_desynced: bytes):
+    header = parser.parse_header(replay_desynced)
+
+    assert isinstance(header["scenario"], dict)
+    assert isinstance(header["mods"], dict)
+    assert isinstance(header["armies"][2], dict)
+
+    for restored in copies(header):
+        assert restored.keys() == header.keys()
+        assert restored["scenario"] == header["scenario"]
+        assert restored["armies"] == header["armies"]
+        assert attributes(restored["map_info"]) == attributes(header["map_info"])
+
+
+def test_parse_header_lazy(replay_desynced: bytes):
+    header = Parser(lazy=True).parse_header(replay_desynced)
+    scenario = header["scenario"]
+
+    assert isinstance(scenario, LuaTable)
+    assert isinstance(scenario, Mapping)
+    assert scenario["size"] == {1.0: 512.0, 2.0: 512.0}
+    assert scenario["size"][1] == 512.0
+    assert scenario["Options"]["Share"] == b"ShareUntilDeath"
+    assert scenario[b"Options"][b"Share"] == b"ShareUntilDeath"
+    assert scenario.get("Options").get("NotAnOption") is None
+    assert "Options" in scenario
+    assert "NotAKey" not in scenario
+    assert b"\xff" not in scenario
+    with pytest.raises(KeyError):
+        scenario["NotAKey"]
+
+    converted = scenario.to_python()
+    assert isinstance(converted, dict)
+    assert isinstance(converted["Options"], dict)
+    assert scenario == converted
+    assert dict(scenario.items()).keys() == converted.keys()
+    assert len(scenario) == len(converted)
+    assert sorted(scenario, key=str) == sorted(converted, key=str)
+
+    assert header["mods"] == {}
+    assert header["armies"][2]["PlayerName"] == b"Gan9sta"
+    assert header["armies"][2].to_python()["PlayerName"] == b"Gan9sta"
+
+    # Fields derived from the Lua tables are the same as without lazy
+    eager = Parser().parse_header(replay_desynced)
+    assert header["map_info"].name == eager["map_info"].name
+    assert header["mod_info"] == eager["mod_info"] == []
+    assert header["game_options"].share == eager["game_options"].share
+    assert header["teams"].teams == eager["teams"].teams
+    assert [p.name for p in header["player_info"]] == [
+        p.name for p in eager["player_info"]
+    ]
+    assert uses_sim_mod(header, "not-a-mod") is False
+
+    # Tables are pickled and copied as dicts
+    for restored in copies(header):
+        assert type(restored["scenario"]["Options"]) is dict
+        assert restored["scenario"] == eager["scenario"]
+        assert restored["armies"] == eager["armies"]
+
+
+def test_parse_lazy(replay_desynced: bytes):
+    replay = Parser(lazy=True, stop_on_desync=False).parse(replay_desynced)
+
+    assert isinstance(replay["header"]["scenario"], LuaTable)
+    assert replay["header"]["player_info"][2].name == "Gan9sta"
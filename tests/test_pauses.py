@@ -0,0 +1,62 @@
+import struct
+
+from fafreplay import body_offset, pause_timeline
+
+
+def set_command_source(source: int) -> bytes:
+    return b"\x01\x04\x00" + bytes([source])
+
+
+def advance(ticks: int) -> bytes:
+    return b"\x00\x07\x00" + struct.pack("<I", ticks)
+
+
+REQUEST_PAUSE = b"\x04\x03\x00"
+RESUME = b"\x05\x03\x00"
+SINGLE_STEP = b"\x06\x03\x00"
+
+
+def test_pause_timeline_no_pauses(replay_desynced: bytes):
+    # The resumes sent at the start of the game are not counted
+    assert pause_timeline(replay_desynced) == []
+
+
+def test_pause_timeline(replay_desynced: bytes):
+    commands = (
+        set_command_source(2) + REQUEST_PAUSE +
+        SINGLE_STEP + advance(1) + SINGLE_STEP + advance(1) +
+        set_command_source(5) + RESUME +
+        advance(10) +
+        set_command_source(3) + REQUEST_PAUSE +
+        set_command_source(6) + REQUEST_PAUSE +
+        set_command_source(3) + RESUME
+    )
+    offset = body_offset(replay_desynced)
+    data = replay_desynced[:offset] + commands + replay_desynced[offset:]
+
+    first, second = pause_timeline(data)
+
+    assert first.source == 2
+    assert first.tick == 0
+    assert first.resumed_by == 5
+    assert first.resume_tick == 2
+    assert first.single_steps == 2
+    assert first.ticks == 2
+    assert first.requests == []
+
+    assert second.source == 3
+    assert second.tick == 12
+    assert second.resumed_by == 3
+    assert second.resume_tick == 12
+    assert second.single_steps == 0
+    assert second.requests == [(12, 6)]
+
+
+def test_pause_timeline_not_resumed(replay_desynced: bytes):
+    data = replay_desynced + set_command_source(1) + REQUEST_PAUSE
+
+    pause, = pause_timeline(data)
+    assert pause.source == 1
+    assert pause.tick == 27868
+    assert pause.resumed_by is None
+    assert pause.ticks is None
@@ -0,0 +1,120 @@
+import struct
+
+from fafreplay import CommandQueueTracker, CommandType, UnitCommandType
+
+
+def test_parse(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    assert len(tracker.units) == 1714
+    assert tracker.units == sorted(tracker.units)
+
+
+def test_history(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    command = tracker.history(0)[0]
+    assert command.unit == 0
+    assert command.id == 0
+    assert command.source == 0
+    assert command.factory is False
+    assert command.type == UnitCommandType.BuildMobile
+    assert command.target == {"x": 295.5, "y": 40.904296875, "z": 427.5}
+    assert command.blueprint == "urb0101"
+    assert command.count == 1
+    assert command.issued_tick == 82
+    assert command.removed_tick == 145
+
+
+def test_queue(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    assert tracker.queue(0, 81) == []
+    assert tracker.current(0, 100).blueprint == "urb0101"
+    assert tracker.current(0, 145).blueprint == "urb1103"
+    assert tracker.current(0, 1000).type == UnitCommandType.Repair
+    assert tracker.current(0, 1000).target == {"id": 9}
+
+    # Factory build orders are queued up
+    queue = tracker.queue(1, 1000)
+    assert [command.blueprint for command in queue] == [
+        "url0105",
+        "url0106",
+        "url0101"
+    ]
+    assert all(command.type == UnitCommandType.BuildFactory for command in queue)
+
+
+def test_factory_queue(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    rally, = tracker.factory_queue(1, 1000)
+    assert rally.factory is True
+    assert rally.type == UnitCommandType.Move
+    assert rally.issued_tick == 488
+
+
+def test_unknown_unit(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    assert tracker.current(123456789, 100) is None
+    assert tracker.history(123456789) == []
+
+
+def test_factories(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    assert len(tracker.factories) == 118
+    assert tracker.factories[:3] == [1, 2, 11]
+
+
+def test_production(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    events = [
+        (event.tick, event.event, event.blueprint, event.count)
+        for event in tracker.production(1)[:7]
+    ]
+    assert events == [
+        (106, "queued", "url0105", 1),
+        (620, "queued", "url0106", 1),
+        (624, "queued", "url0107", 1),
+        (636, "queued", "url0101", 1),
+        (639, "count", "url0107", 0),
+        (639, "removed", "url0107", 0),
+        (1413, "count", "url0106", 0),
+    ]
+    assert all(event.factory == 1 for event in tracker.production(1))
+    assert all(event.source == 0 for event in tracker.production(1))
+
+
+def test_production_all(replay_desynced: bytes):
+    tracker = CommandQueueTracker.parse(replay_desynced)
+
+    events = tracker.production()
+    assert len([e for e in events if e.event == "queued"]) == 2101
+    assert [e.tick for e in events] == sorted(e.tick for e in events)
+    assert {e.factory for e in events} == set(tracker.factories)
+
+
+def test_extreme_count_delta(replay_desynced: bytes):
+    def count_command(type_: int, delta: int) -> bytes:
+        return bytes([type_]) + struct.pack("<HIi", 11, 13, delta)
+
+    # Deltas that would overflow the count saturate instead
+    data = (
+        replay_desynced +
+        count_command(CommandType.DecreaseCommandCount, -2**31) +
+        count_command(CommandType.IncreaseCommandCount, 2**31 - 1)
+    )
+    tracker = CommandQueueTracker.parse(data)
+    assert tracker.current(1, 30000).count == 2**31 - 1
+
+    data = (
+        replay_desynced +
+        count_command(CommandType.DecreaseCommandCount, -2**31) +
+        count_command(CommandType.IncreaseCommandCount, -2**31)
+    )
+    tracker = CommandQueueTracker.parse(data)
+    assert tracker.current(1, 30000) is None
+    assert tracker.history(1)[-1].removed_tick == 27868
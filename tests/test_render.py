@@ -0,0 +1,49 @@
+import xml.etree.ElementTree as ET
+
+from fafreplay import Parser, body_offset, render
+
+SVG = "{http://www.w3.org/2000/svg}"
+
+
+def elements(svg: str, tag: str):
+    return ET.fromstring(svg).findall(SVG + tag)
+
+
+def test_command_map(replay_desynced: bytes):
+    svg = render.command_map(replay_desynced, size=256)
+    root = ET.fromstring(svg)
+
+    assert root.get("width") == "256"
+    assert root.get("height") == "256"
+
+    names = [text.text for text in elements(svg, "text")]
+    header = Parser().parse_header(replay_desynced)
+    assert names == [p.name for p in header["player_info"]]
+
+    colors = {c.get("fill") for c in elements(svg, "circle")}
+    assert colors == set(render.PALETTE[:8])
+    builds = [r for r in elements(svg, "rect") if r.get("fill") == "none"]
+    assert builds
+    assert all(0 <= float(r.get("x")) + 2 <= 256 for r in builds)
+
+
+def test_timeline(replay_desynced: bytes):
+    svg = render.timeline(replay_desynced, width=400, height=100)
+    root = ET.fromstring(svg)
+
+    assert root.get("width") == "400"
+    assert len(elements(svg, "polyline")) == 8
+    # Four players have a terminated command source
+    assert len(elements(svg, "line")) == 4
+    # Only the background, there are no pauses
+    assert len(elements(svg, "rect")) == 1
+
+
+def test_timeline_pause(replay_desynced: bytes):
+    offset = body_offset(replay_desynced)
+    pause = b"\x01\x04\x00\x02" + b"\x04\x03\x00" + b"\x05\x03\x00"
+    data = replay_desynced[:offset] + pause + replay_desynced[offset:]
+
+    svg = render.timeline(data)
+
+    assert len(elements(svg, "rect")) == 2
@@ -1,4 +1,15 @@
-from fafreplay import body_offset, body_ticks
+from collections import Counter
+
+import pytest
+
+from fafreplay import (
+    Parser,
+    body_info,
+    body_offset,
+    body_ticks,
+    commands,
+    header_info
+)
 
 
 def test_body_offset(replay):
@@ -15,3 +26,72 @@ def test_body_ticks(replay):
 
 def test_body_ticks_bytearray(replay):
     assert body_ticks(bytearray(replay[4923:])) == 28917
+
+
+def test_body_info(replay_desynced):
+    body = replay_desynced[body_offset(replay_desynced):]
+    info = body_info(body)
+
+    parser = Parser(
+        commands=range(commands.MAX + 1),
+        save_commands=True,
+        stop_on_desync=False
+    )
+    replay = parser.parse(replay_desynced)
+    sim = replay["body"]["sim"]
+
+    command_counts = info.pop("command_counts")
+    assert info == sim
+    assert info["tick"] == body_ticks(body) == 27868
+    assert command_counts == Counter(
+        command["name"] for command in replay["body"]["commands"]
+    )
+
+
+def test_body_info_bytearray(replay_desynced):
+    body = replay_desynced[body_offset(replay_desynced):]
+    assert body_info(bytearray(body)) == body_info(body)
+
+
+def test_body_info_truncated(replay_desynced):
+    body = replay_desynced[body_offset(replay_desynced):]
+    # Cut off in the middle of the first command
+    assert body_info(body[:2])["tick"] == 0
+    assert body_info(body[:-1])["tick"] == body_ticks(body[:-1])
+
+
+def test_body_info_type_error():
+    with pytest.raises(TypeError):
+        body_info("not bytes")
+
+
+def test_header_info(replay_desynced):
+    info = header_info(replay_desynced)
+    header = Parser().parse_header(replay_desynced)
+
+    assert info == {
+        key: header[key] for key in [
+            "scfa_version",
+            "replay_version",
+            "map_file",
+            "players",
+            "army_count",
+        ]
+    }
+    assert sorted(info["players"]) == sorted(
+        p.name for p in header["player_info"]
+    )
+
+
+def test_header_info_bytearray(replay_desynced):
+    assert header_info(bytearray(replay_desynced)) == header_info(replay_desynced)
+
+
+def test_header_info_truncated(replay_desynced):
+    with pytest.raises(EOFError):
+        header_info(replay_desynced[:1000])
+
+
+def test_header_info_type_error():
+    with pytest.raises(TypeError):
+        header_info("not bytes")
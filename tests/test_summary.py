@@ -0,0 +1,74 @@
+from collections import Counter
+
+from fafreplay import (
+    CommandType,
+    Parser,
+    body_offset,
+    commands,
+    pause_timeline,
+    summarize
+)
+
+
+def test_summarize(replay_desynced: bytes):
+    summary = summarize(replay_desynced)
+
+    parser = Parser(
+        commands=range(commands.MAX + 1),
+        save_commands=True,
+        stop_on_desync=False
+    )
+    replay = parser.parse(replay_desynced)
+    header = replay["header"]
+    sim = replay["body"]["sim"]
+
+    assert summary.ticks == sim["tick"] == 27868
+    assert summary.duration == 2786.8
+    assert summary.map.name == "canis 4v4 spezial edition"
+    assert [p.name for p in summary.players] == [
+        p.name for p in header["player_info"]
+    ]
+    assert summary.teams.size == "4v4"
+    assert summary.desynced
+    assert summary.desync_ticks == sim["desync_ticks"]
+    assert summary.quit_ticks == sim["players_last_tick"]
+    assert summary.pauses == len(pause_timeline(replay_desynced)) == 0
+
+    counts = {}
+    source = 0
+    for command in replay["body"]["commands"]:
+        name = command["name"]
+        if name == CommandType.SetCommandSource:
+            source = command["id"]
+        elif name != CommandType.Advance:
+            counts.setdefault(source, Counter())[name] += 1
+    assert summary.command_counts == counts
+
+
+def test_summarize_apm(replay_desynced: bytes):
+    summary = summarize(replay_desynced)
+
+    assert sorted(summary.apm) == list(range(8))
+    counts = summary.command_counts[1]
+    actions = sum(
+        counts.get(name, 0) for name in [
+            CommandType.IssueCommand,
+            CommandType.IssueFactoryCommand,
+            CommandType.IncreaseCommandCount,
+            CommandType.DecreaseCommandCount,
+            CommandType.SetCommandTarget,
+            CommandType.SetCommandType,
+            CommandType.SetCommandCells,
+            CommandType.RemoveCommandFromQueue,
+        ]
+    )
+    # Source 1 left the game on tick 20691
+    assert abs(summary.apm[1] - actions / (20691 / 600)) < 0.01
+
+
+def test_summarize_pauses(replay_desynced: bytes):
+    offset = body_offset(replay_desynced)
+    pause = b"\x01\x04\x00\x02" + b"\x04\x03\x00" + b"\x05\x03\x00"
+    data = replay_desynced[:offset] + pause + replay_desynced[offset:]
+
+    assert summarize(data).pauses == 1